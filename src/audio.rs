@@ -0,0 +1,371 @@
+///
+/// Music & sound effects, mixed through master, music & SFX buses
+///
+use bevy::{
+	audio::{AddAudioSource, Decodable, Source},
+	prelude::*,
+	reflect::TypeUuid,
+	utils::HashMap,
+};
+use bevy_ecs_ldtk::{prelude::LdtkFields, LdtkLevel};
+use bevy_rapier2d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	game::GameplayEvent,
+	level::material::{MaterialTable, Surface},
+	player::{Player, PLAYER_MAX_SPEED},
+	states::AppState,
+};
+
+/// Sound played for each gameplay event, events that aren't listed are silent
+const DEFAULT_SOUNDS: [(GameplayEvent, &str); 4] = [
+	(GameplayEvent::Jumped, "sounds/jump.ogg"),
+	(GameplayEvent::GroundPounded, "sounds/ground_pound.ogg"),
+	(GameplayEvent::Launched, "sounds/launchpad.ogg"),
+	(GameplayEvent::Teleported, "sounds/portal.ogg"),
+];
+
+pub struct MixerPlugin;
+
+impl Plugin for MixerPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_audio_source::<MusicLayer>()
+			.init_resource::<SoundBank>()
+			.init_resource::<Music>()
+			.add_startup_system(load_sound_bank)
+			// without an audio output (headless), nothing is played
+			.add_systems(
+				(
+					music_select,
+					music_start.after(music_select),
+					music_intensity,
+					music_update.after(music_start),
+					play_sounds,
+				)
+					.distributive_run_if(resource_exists::<Audio>()),
+			);
+	}
+}
+
+/// Volume of each bus, the music & SFX buses are scaled by the master one
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct AudioSettings {
+	pub master: f32,
+	pub music: f32,
+	pub sfx: f32,
+	pub music_muted: bool,
+}
+
+impl Default for AudioSettings {
+	fn default() -> Self {
+		Self {
+			master: 1.0,
+			music: 0.5,
+			sfx: 0.5,
+			music_muted: false,
+		}
+	}
+}
+
+impl AudioSettings {
+	pub fn music_volume(&self) -> f32 {
+		self.master * self.music
+	}
+
+	pub fn sfx_volume(&self) -> f32 {
+		self.master * self.sfx
+	}
+}
+
+/// Sound effect of each gameplay event, loaded at startup rather than when they play
+/// Replacing a sound in the bank (e.g. for a level) changes it everywhere
+#[derive(Default, Resource)]
+pub struct SoundBank {
+	pub events: HashMap<GameplayEvent, Handle<AudioSource>>,
+	/// Sounds of the events that depend on the material the player stands on
+	pub surfaces: HashMap<(Surface, GameplayEvent), Handle<AudioSource>>,
+}
+
+fn load_sound_bank(
+	mut bank: ResMut<SoundBank>,
+	materials: Res<MaterialTable>,
+	asset_server: Res<AssetServer>,
+) {
+	for (event, path) in DEFAULT_SOUNDS {
+		bank.events.insert(event, asset_server.load(path));
+	}
+	for surface in (0..materials.materials.len()).map(Surface) {
+		for event in [GameplayEvent::Landed, GameplayEvent::Footstep] {
+			if let Some(path) = materials.sound(surface, event) {
+				bank.surfaces
+					.insert((surface, event), asset_server.load(path));
+			}
+		}
+	}
+}
+
+/// The one place gameplay sounds are played from, on the SFX bus
+/// Landing & footsteps sound like the material the player stands on
+fn play_sounds(
+	mut ev_gameplay: EventReader<GameplayEvent>,
+	bank: Res<SoundBank>,
+	audio: Res<Audio>,
+	settings: Res<AudioSettings>,
+	q_player: Query<&Player>,
+) {
+	let surface = q_player.get_single().ok().and_then(|player| player.surface);
+	for event in ev_gameplay.iter() {
+		let sound = surface
+			.and_then(|surface| bank.surfaces.get(&(surface, *event)))
+			.or_else(|| bank.events.get(event));
+		let Some(sound) = sound else {
+			continue;
+		};
+		audio.play_with_settings(
+			sound.clone(),
+			PlaybackSettings::ONCE.with_volume(settings.sfx_volume()),
+		);
+	}
+}
+
+/// A piece of music, looped
+struct MusicTrack {
+	path: &'static str,
+	/// Playback speed, which also shifts the pitch
+	speed: f32,
+	/// Frequency above which the track is played again as its intensity layer, in Hz
+	layer_cutoff: u32,
+}
+
+/// Tracks that levels can select with their `music` field
+const MUSIC_TRACKS: [(&str, MusicTrack); 2] = [
+	(
+		"main",
+		MusicTrack {
+			path: "sounds/music.ogg",
+			speed: 1.0,
+			layer_cutoff: 2000,
+		},
+	),
+	// slower & lower, for the menus & the end
+	(
+		"calm",
+		MusicTrack {
+			path: "sounds/music.ogg",
+			speed: 0.8,
+			layer_cutoff: 1500,
+		},
+	),
+];
+
+/// Track of levels that don't select one
+const DEFAULT_TRACK: &str = "main";
+
+/// Track of the menus & the leaderboard
+const MENU_TRACK: &str = "calm";
+
+/// How long tracks take to fade in & out when the music changes, in seconds
+const CROSSFADE_DURATION: f32 = 2.0;
+
+/// How fast the intensity follows the player's speed
+const INTENSITY_SMOOTHING: f32 = 2.0;
+
+/// The highs of a track, played along it louder as the player goes faster
+#[derive(TypeUuid)]
+#[uuid = "4013a9d3-3f85-481f-985c-a9c910ff2b32"]
+pub struct MusicLayer {
+	source: AudioSource,
+	cutoff: u32,
+}
+
+impl Decodable for MusicLayer {
+	type DecoderItem = f32;
+	type Decoder = Box<dyn Source<Item = f32> + Send>;
+
+	fn decoder(&self) -> Self::Decoder {
+		Box::new(
+			self.source
+				.decoder()
+				.convert_samples()
+				.high_pass(self.cutoff),
+		)
+	}
+}
+
+/// A track being played, fading in or out
+struct MusicVoice {
+	track: &'static str,
+	base: Handle<AudioSink>,
+	layer: Handle<AudioSink>,
+	/// 0 is silent, 1 is fully faded in
+	fade: f32,
+}
+
+/// The music being played
+#[derive(Default, Resource)]
+pub struct Music {
+	/// Track waiting for its file, so that its base & layer start together
+	pending: Option<(&'static str, Handle<AudioSource>)>,
+	current: Option<MusicVoice>,
+	fading_out: Vec<MusicVoice>,
+	/// How fast the player goes, from 0 to 1
+	intensity: f32,
+}
+
+fn find_track(name: &str) -> Option<(&'static str, &'static MusicTrack)> {
+	MUSIC_TRACKS
+		.iter()
+		.find(|(track, _)| *track == name)
+		.map(|(track, music)| (*track, music))
+}
+
+/// Switches to the track of the current level, or of the menus
+fn music_select(
+	mut music: ResMut<Music>,
+	mut last_requested: Local<Option<String>>,
+	state: Res<State<AppState>>,
+	q_level: Query<&Handle<LdtkLevel>>,
+	ldtk_levels: Res<Assets<LdtkLevel>>,
+	asset_server: Res<AssetServer>,
+) {
+	let requested = match state.0 {
+		AppState::Game => {
+			// keep the current music until the level is loaded
+			let Some(level) = q_level.iter().find_map(|h| ldtk_levels.get(h)) else {
+				return;
+			};
+			match level.level.get_maybe_string_field("music") {
+				Ok(Some(track)) => track.clone(),
+				_ => DEFAULT_TRACK.to_owned(),
+			}
+		}
+		_ => MENU_TRACK.to_owned(),
+	};
+	if last_requested.as_ref() == Some(&requested) {
+		return;
+	}
+	*last_requested = Some(requested.clone());
+
+	let (name, track) = find_track(&requested).unwrap_or_else(|| {
+		warn!("unknown music track `{requested}`, playing `{DEFAULT_TRACK}`");
+		find_track(DEFAULT_TRACK).unwrap()
+	});
+	if music.current.as_ref().map(|voice| voice.track) == Some(name) {
+		music.pending = None;
+	} else if music.pending.as_ref().map(|(pending, _)| *pending) != Some(name) {
+		music.pending = Some((name, asset_server.load(track.path)));
+	}
+}
+
+/// Starts the pending track once its file is loaded, fading out the previous one
+fn music_start(
+	mut music: ResMut<Music>,
+	audio: Res<Audio>,
+	layer_audio: Res<Audio<MusicLayer>>,
+	audio_sources: Res<Assets<AudioSource>>,
+	mut layers: ResMut<Assets<MusicLayer>>,
+	audio_sinks: Res<Assets<AudioSink>>,
+) {
+	let Some((name, handle)) = &music.pending else {
+		return;
+	};
+	let Some(source) = audio_sources.get(handle) else {
+		return;
+	};
+	let (name, track) = find_track(name).unwrap();
+
+	let settings = PlaybackSettings {
+		repeat: true,
+		volume: 0.0,
+		speed: track.speed,
+	};
+	// started on the same frame so that they stay in sync
+	let mut base = audio.play_with_settings(handle.clone(), settings);
+	let layer = layers.add(MusicLayer {
+		source: source.clone(),
+		cutoff: track.layer_cutoff,
+	});
+	let mut layer = layer_audio.play_with_settings(layer, settings);
+	base.make_strong(&audio_sinks);
+	layer.make_strong(&audio_sinks);
+
+	let voice = MusicVoice {
+		track: name,
+		base,
+		layer,
+		fade: 0.0,
+	};
+	music.pending = None;
+	if let Some(previous) = music.current.replace(voice) {
+		music.fading_out.push(previous);
+	}
+}
+
+/// Follows the player's speed, smoothly so that the music doesn't jump around
+fn music_intensity(
+	time: Res<Time>,
+	mut music: ResMut<Music>,
+	q_player: Query<&Velocity, With<Player>>,
+) {
+	let target = match q_player.get_single() {
+		Ok(velocity) => (velocity.linvel.length() / PLAYER_MAX_SPEED).min(1.0),
+		Err(_) => 0.0,
+	};
+	let t = (INTENSITY_SMOOTHING * time.raw_delta_seconds()).min(1.0);
+	music.intensity += (target - music.intensity) * t;
+}
+
+/// Crossfades the tracks & keeps them in sync with the settings
+/// Sinks only exist once the music started playing, so this checks every frame
+fn music_update(
+	time: Res<Time>,
+	settings: Res<AudioSettings>,
+	audio_sinks: Res<Assets<AudioSink>>,
+	mut music: ResMut<Music>,
+) {
+	// the music keeps fading while the game is paused
+	let step = time.raw_delta_seconds() / CROSSFADE_DURATION;
+	let intensity = music.intensity;
+	let Music {
+		current,
+		fading_out,
+		..
+	} = music.as_mut();
+
+	if let Some(voice) = current {
+		voice.fade = (voice.fade + step).min(1.0);
+	}
+	fading_out.retain_mut(|voice| {
+		voice.fade -= step;
+		if voice.fade > 0.0 {
+			return true;
+		}
+		for sink in [&voice.base, &voice.layer] {
+			if let Some(sink) = audio_sinks.get(sink) {
+				sink.stop();
+			}
+		}
+		false
+	});
+
+	let apply = |handle: &Handle<AudioSink>, volume: f32| {
+		let Some(sink) = audio_sinks.get(handle) else {
+			return;
+		};
+		if sink.volume() != volume {
+			sink.set_volume(volume);
+		}
+		match settings.music_muted {
+			true if !sink.is_paused() => sink.pause(),
+			false if sink.is_paused() => sink.play(),
+			_ => {}
+		}
+	};
+	for voice in current.iter().chain(fading_out.iter()) {
+		let volume = settings.music_volume() * voice.fade;
+		apply(&voice.base, volume);
+		apply(&voice.layer, volume * intensity);
+	}
+}
@@ -0,0 +1,179 @@
+///
+/// The game camera follows the player, looking ahead in the direction they are going
+/// and staying inside the level
+/// Camera zones of the level change its zoom & position while the player is inside
+///
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_ecs_ldtk::{prelude::LdtkFields, LdtkLevel};
+use bevy_rapier2d::prelude::*;
+
+use super::GameplayEvent;
+use crate::{
+	level::{
+		camera_zone::{CameraZone, LockedAxis},
+		LevelSize,
+	},
+	player::Player,
+	states::AppState,
+};
+
+/// Scale of the projection at zoom 1
+pub const BASE_SCALE: f32 = 16.0;
+
+/// The player moving farther than this in one frame (e.g. loading a savestate) cuts to them
+const CUT_DISTANCE: f32 = 8.0;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(
+			camera_follow
+				.in_base_set(CoreSet::PostUpdate)
+				.after(PhysicsSet::Writeback)
+				.before(TransformSystem::TransformPropagate)
+				.run_if(in_state(AppState::Game)),
+		);
+	}
+}
+
+#[derive(Component)]
+pub struct CameraController {
+	/// How fast the camera catches up with its target, higher is snappier
+	pub smoothing: f32,
+	/// How far ahead the camera looks, in seconds of the player's velocity
+	pub look_ahead: f32,
+	/// Maximum distance between the player and the camera's target
+	pub max_look_ahead: f32,
+	/// Position to show instead of the player's, e.g. during the level intro
+	pub focus: Option<Vec2>,
+	/// Where the camera is heading, None until it found the player
+	target: Option<Vec2>,
+	zoom: f32,
+	/// Remaining time the camera stays still, in seconds
+	hold: f32,
+	/// Last position of the player, to detect teleports
+	last_player_pos: Vec2,
+}
+
+impl Default for CameraController {
+	fn default() -> Self {
+		Self {
+			smoothing: 6.0,
+			look_ahead: 0.3,
+			max_look_ahead: 6.0,
+			focus: None,
+			target: None,
+			zoom: 1.0,
+			hold: 0.0,
+			last_player_pos: Vec2::ZERO,
+		}
+	}
+}
+
+impl CameraController {
+	/// Keeps the camera still for a moment (hit-stop), unless it has to cut
+	pub fn hold_for(&mut self, seconds: f32) {
+		self.hold = self.hold.max(seconds);
+	}
+}
+
+pub fn camera_follow(
+	time: Res<Time>,
+	level_size: LevelSize,
+	mut ev_gameplay: EventReader<GameplayEvent>,
+	mut q_camera: Query<(
+		&mut CameraController,
+		&mut Transform,
+		&mut OrthographicProjection,
+	)>,
+	q_player: Query<(&Transform, &Velocity), (With<Player>, Without<CameraController>)>,
+	q_zones: Query<&CameraZone>,
+	q_level: Query<&Handle<LdtkLevel>>,
+	ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+	let teleported = ev_gameplay
+		.iter()
+		.any(|ev| *ev == GameplayEvent::Teleported);
+	let Ok((mut controller, mut camera_tr, mut projection)) = q_camera.get_single_mut() else {
+		return;
+	};
+	let Ok((player_tr, player_vel)) = q_player.get_single() else {
+		return;
+	};
+
+	let player_pos = player_tr.translation.truncate();
+	let look_ahead =
+		(player_vel.linvel * controller.look_ahead).clamp_length_max(controller.max_look_ahead);
+	let cut = teleported
+		|| controller.focus.is_some()
+		|| controller.target.is_none()
+		|| player_pos.distance(controller.last_player_pos) > CUT_DISTANCE;
+	controller.last_player_pos = player_pos;
+
+	let level_zoom = q_level
+		.iter()
+		.find_map(|h| ldtk_levels.get(h))
+		.and_then(|level| level.level.get_maybe_float_field("zoom").ok().copied())
+		.flatten()
+		.unwrap_or(1.0);
+	let followed = controller.focus.unwrap_or(player_pos);
+	let zone = q_zones.iter().find(|zone| zone.rect.contains(followed));
+
+	// the look-ahead is smoothed too, so that turning around doesn't swing the camera
+	let mut target = match controller.focus {
+		Some(focus) => focus,
+		None => player_pos + look_ahead,
+	};
+	let mut target_zoom = level_zoom;
+	if let Some(zone) = zone {
+		match zone.locked_axis {
+			Some(LockedAxis::X) => target.x = zone.rect.center().x,
+			Some(LockedAxis::Y) => target.y = zone.rect.center().y,
+			None => {}
+		}
+		target += zone.offset;
+		target_zoom = zone.zoom;
+	}
+	let holding = controller.hold > 0.0;
+	controller.hold = match cut {
+		true => 0.0,
+		false => (controller.hold - time.raw_delta_seconds()).max(0.0),
+	};
+	let (pos, zoom) = match (cut, controller.target) {
+		(false, Some(previous)) if holding => (previous, controller.zoom),
+		(false, Some(previous)) => {
+			let t = 1.0 - (-controller.smoothing * time.delta_seconds()).exp();
+			(
+				previous.lerp(target, t),
+				controller.zoom + (target_zoom - controller.zoom) * t,
+			)
+		}
+		_ => (target, target_zoom),
+	};
+	controller.target = Some(pos);
+	controller.zoom = zoom;
+
+	// the visible area is only updated later in the frame, so it is rescaled here
+	let half_view = projection.area.half_size() / projection.scale * BASE_SCALE / zoom;
+	projection.scale = BASE_SCALE / zoom;
+
+	camera_tr.translation =
+		clamp_to_level(pos, &level_size, half_view).extend(camera_tr.translation.z);
+}
+
+/// Keeps the view inside the level, centering it on axes where the level is smaller than the view
+fn clamp_to_level(pos: Vec2, level_size: &LevelSize, half_view: Vec2) -> Vec2 {
+	let Some(size) = level_size.try_get() else {
+		return pos;
+	};
+	let size = size.as_vec2();
+	let clamp_axis = |pos: f32, size: f32, half_view: f32| match size > half_view * 2.0 {
+		true => pos.clamp(half_view, size - half_view),
+		false => size / 2.0,
+	};
+	Vec2::new(
+		clamp_axis(pos.x, size.x, half_view.x),
+		clamp_axis(pos.y, size.y, half_view.y),
+	)
+}
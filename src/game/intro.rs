@@ -0,0 +1,293 @@
+///
+/// Level intro: the camera pans from the finish to the start, then counts down before the run
+/// The run (clock, physics & timer) is frozen until the intro is over
+///
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+	camera::CameraController,
+	pause::{self, Paused},
+	practice::{ActiveCheckpoint, Segment},
+	StartTime,
+};
+use crate::{
+	input::{Action, InputSet},
+	leaderboard::Leaderboard,
+	level::finish::Finish,
+	player::Player,
+	states::AppState,
+	tas::TasMode,
+};
+
+/// Speed of the camera along the path, in world units per second
+const PAN_SPEED: f32 = 24.0;
+
+/// Bounds of the pan's duration, in seconds, so that it is neither a blur nor a chore
+const PAN_MIN_DURATION: f32 = 1.5;
+const PAN_MAX_DURATION: f32 = 6.0;
+
+/// Length of the countdown, in seconds
+const COUNTDOWN: f32 = 3.0;
+
+pub struct IntroPlugin;
+
+impl Plugin for IntroPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Intro>()
+			.add_system(intro_start.in_schedule(OnEnter(AppState::Game)))
+			.add_system(
+				intro_update
+					.in_set(InputSet)
+					.after(pause::pause_update)
+					.run_if(in_state(AppState::Game))
+					.run_if(|paused: Res<Paused>| !paused.0),
+			)
+			.add_system(intro_ui.run_if(in_state(AppState::Game)))
+			.add_system(intro_exit.in_schedule(OnExit(AppState::Game)));
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct IntroSettings {
+	/// Show the level from the finish to the start
+	pub pan: bool,
+	/// Count down from 3 before the run starts
+	pub countdown: bool,
+	/// Also count down when restarting the same level
+	pub restart_countdown: bool,
+}
+
+impl Default for IntroSettings {
+	fn default() -> Self {
+		Self {
+			pan: true,
+			countdown: true,
+			restart_countdown: false,
+		}
+	}
+}
+
+/// Where the intro is at
+#[derive(Default, Resource)]
+pub enum Intro {
+	/// The run is going
+	#[default]
+	Done,
+	/// Waiting for the level to be loaded
+	Loading {
+		pan: bool,
+	},
+	Pan {
+		/// From the finish to the start
+		path: Vec<Vec2>,
+		elapsed: f32,
+		duration: f32,
+	},
+	Countdown {
+		remaining: f32,
+	},
+}
+
+impl Intro {
+	pub fn running(&self) -> bool {
+		!matches!(self, Intro::Done)
+	}
+}
+
+/// The pan & countdown are only played the first time a level is entered, not when restarting it
+/// unless asked for
+fn intro_start(
+	mut intro: ResMut<Intro>,
+	mut last_level: Local<Option<LevelSelection>>,
+	settings: Res<IntroSettings>,
+	level: Res<LevelSelection>,
+	active_checkpoint: Res<ActiveCheckpoint>,
+	tas_mode: Res<TasMode>,
+) {
+	let new_level = last_level.as_ref() != Some(level.as_ref());
+	*last_level = Some(level.clone());
+
+	// restarting from a checkpoint should be quick, and tool-assisted runs have their own timing
+	let countdown = settings.countdown && (new_level || settings.restart_countdown);
+	*intro = match (settings.pan && new_level, countdown) {
+		_ if active_checkpoint.0.is_some() || tas_mode.0 => Intro::Done,
+		(false, false) => Intro::Done,
+		(pan, _) => Intro::Loading { pan },
+	};
+}
+
+/// Freezes the run like pausing does, moves the intro along, and skips it when Jump is pressed
+fn intro_update(
+	mut intro: ResMut<Intro>,
+	settings: Res<IntroSettings>,
+	level: Res<LevelSelection>,
+	leaderboard: Res<Leaderboard>,
+	mut actions: ResMut<Input<Action>>,
+	mut time: ResMut<Time>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+	mut start_time: ResMut<StartTime>,
+	mut segment: ResMut<Segment>,
+	mut q_camera: Query<&mut CameraController>,
+	q_player: Query<&Transform, With<Player>>,
+	q_finish: Query<&Transform, With<Finish>>,
+) {
+	if !intro.running() {
+		return;
+	}
+
+	let delta = time.raw_delta();
+	if actions.just_pressed(Action::Jump) {
+		*intro = Intro::Done;
+	}
+	match intro.as_mut() {
+		Intro::Done => {}
+		Intro::Loading { pan } => {
+			if let (Ok(player_tr), Ok(finish_tr)) = (q_player.get_single(), q_finish.get_single()) {
+				*intro = match pan {
+					true => {
+						let path = pan_path(
+							&leaderboard,
+							&level,
+							finish_tr.translation.truncate(),
+							player_tr.translation.truncate(),
+						);
+						let duration = (path_length(&path) / PAN_SPEED)
+							.clamp(PAN_MIN_DURATION, PAN_MAX_DURATION);
+						Intro::Pan {
+							path,
+							elapsed: 0.0,
+							duration,
+						}
+					}
+					false => Intro::Countdown {
+						remaining: COUNTDOWN,
+					},
+				};
+			}
+		}
+		Intro::Pan {
+			path,
+			elapsed,
+			duration,
+		} => {
+			*elapsed += delta.as_secs_f32();
+			// eased so that the camera doesn't start & stop abruptly
+			let t = (*elapsed / *duration).min(1.0);
+			let t = t * t * (3.0 - 2.0 * t);
+			if let Ok(mut controller) = q_camera.get_single_mut() {
+				controller.focus = Some(point_along(path, t * path_length(path)));
+			}
+			if *elapsed >= *duration {
+				*intro = match settings.countdown {
+					true => Intro::Countdown {
+						remaining: COUNTDOWN,
+					},
+					false => Intro::Done,
+				};
+			}
+		}
+		Intro::Countdown { remaining } => {
+			*remaining -= delta.as_secs_f32();
+			if *remaining <= 0.0 {
+				*intro = Intro::Done;
+			}
+		}
+	}
+
+	if !matches!(*intro, Intro::Pan { .. }) {
+		if let Ok(mut controller) = q_camera.get_single_mut() {
+			controller.focus = None;
+		}
+	}
+
+	match intro.running() {
+		true => {
+			time.pause();
+			rapier_config.physics_pipeline_active = false;
+			// the timer only starts once the intro is over
+			start_time.0 += delta;
+			segment.start += delta;
+		}
+		false => {
+			time.unpause();
+			rapier_config.physics_pipeline_active = true;
+		}
+	}
+	// neither skipping nor the intro's inputs should make the player move
+	actions.reset_all();
+}
+
+/// Path the best run took from the start to the finish, reversed
+/// A straight line if the level hasn't been finished yet
+fn pan_path(
+	leaderboard: &Leaderboard,
+	level: &LevelSelection,
+	finish: Vec2,
+	start: Vec2,
+) -> Vec<Vec2> {
+	let best = match level {
+		LevelSelection::Index(level) => leaderboard.get_best(*level),
+		_ => None,
+	};
+	match best {
+		Some(replay) if replay.positions.len() >= 2 => {
+			replay.positions.iter().rev().copied().collect()
+		}
+		_ => vec![finish, start],
+	}
+}
+
+fn path_length(path: &[Vec2]) -> f32 {
+	path.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+/// Point at `distance` along the path
+fn point_along(path: &[Vec2], mut distance: f32) -> Vec2 {
+	for w in path.windows(2) {
+		let length = w[0].distance(w[1]);
+		if distance <= length && length > 0.0 {
+			return w[0].lerp(w[1], distance / length);
+		}
+		distance -= length;
+	}
+	path.last().copied().unwrap_or_default()
+}
+
+fn intro_ui(mut egui_ctx: EguiContexts, intro: Res<Intro>) {
+	let text = match *intro {
+		Intro::Pan { .. } => "Press Jump to skip".to_owned(),
+		Intro::Countdown { remaining } => format!("{}", remaining.ceil().max(1.0) as u32),
+		_ => return,
+	};
+	let size = match *intro {
+		Intro::Countdown { .. } => 64.0,
+		_ => 16.0,
+	};
+	egui::Window::new("intro")
+		.movable(false)
+		.collapsible(false)
+		.resizable(false)
+		.title_bar(false)
+		.frame(egui::Frame::none())
+		.anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+		.show(egui_ctx.ctx_mut(), |ui| {
+			ui.label(egui::RichText::new(text).size(size));
+		});
+}
+
+fn intro_exit(
+	mut intro: ResMut<Intro>,
+	mut time: ResMut<Time>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+) {
+	if intro.running() {
+		*intro = Intro::Done;
+		time.unpause();
+		rapier_config.physics_pipeline_active = true;
+	}
+}
@@ -0,0 +1,160 @@
+///
+/// Screen shake & hit-stop, giving impacts some weight
+/// Both only move the camera: the simulation & the timer never stop, so runs stay deterministic
+///
+use bevy::{prelude::*, transform::TransformSystem};
+use serde::{Deserialize, Serialize};
+
+use super::{
+	camera::{self, CameraController},
+	GameplayEvent,
+};
+use crate::states::AppState;
+
+/// Impact of each gameplay event, events that aren't listed have none
+const IMPACTS: [(GameplayEvent, Impact); 3] = [
+	(
+		GameplayEvent::GroundPounded,
+		Impact {
+			trauma: 0.35,
+			hit_stop: 0.0,
+		},
+	),
+	(
+		GameplayEvent::Launched,
+		Impact {
+			trauma: 0.5,
+			hit_stop: 0.06,
+		},
+	),
+	// the camera cuts to the other portal, holding it would show the wrong place
+	(
+		GameplayEvent::Teleported,
+		Impact {
+			trauma: 0.3,
+			hit_stop: 0.0,
+		},
+	),
+];
+
+/// How much trauma goes away each second
+const TRAUMA_DECAY: f32 = 1.5;
+
+/// Offset & rotation of the camera at full trauma, in world units & radians
+const MAX_OFFSET: f32 = 0.6;
+const MAX_ANGLE: f32 = 0.05;
+
+/// How fast the camera shakes, in oscillations per second
+const SHAKE_FREQUENCY: f32 = 18.0;
+
+pub struct JuicePlugin;
+
+impl Plugin for JuicePlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(
+			juice_impacts
+				.before(camera::camera_follow)
+				.in_base_set(CoreSet::PostUpdate)
+				.run_if(in_state(AppState::Game)),
+		)
+		.add_system(
+			camera_unshake
+				.before(camera::camera_follow)
+				.in_base_set(CoreSet::PostUpdate)
+				.run_if(in_state(AppState::Game)),
+		)
+		.add_system(
+			camera_shake
+				.after(camera::camera_follow)
+				.before(TransformSystem::TransformPropagate)
+				.in_base_set(CoreSet::PostUpdate)
+				.run_if(in_state(AppState::Game)),
+		);
+	}
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct MotionSettings {
+	/// Disables screen shake & hit-stop
+	pub reduced_motion: bool,
+}
+
+/// How an event shakes the screen
+#[derive(Clone, Copy)]
+struct Impact {
+	/// Added to the camera's trauma, from 0 to 1
+	trauma: f32,
+	/// How long the camera stays still, in seconds
+	hit_stop: f32,
+}
+
+/// Shakes the camera, harder the more trauma it has
+/// Anything can add trauma to make the screen shake, it wears off by itself
+#[derive(Component, Default)]
+pub struct Shake {
+	trauma: f32,
+	/// Offset applied to the camera this frame, taken back before it follows again
+	offset: Vec3,
+}
+
+impl Shake {
+	pub fn add_trauma(&mut self, amount: f32) {
+		self.trauma = (self.trauma + amount).min(1.0);
+	}
+}
+
+fn juice_impacts(
+	mut ev_gameplay: EventReader<GameplayEvent>,
+	settings: Res<MotionSettings>,
+	mut q_camera: Query<(&mut Shake, &mut CameraController)>,
+) {
+	let Ok((mut shake, mut controller)) = q_camera.get_single_mut() else {
+		return;
+	};
+	for event in ev_gameplay.iter() {
+		if settings.reduced_motion {
+			continue;
+		}
+		if let Some((_, impact)) = IMPACTS.iter().find(|(e, _)| e == event) {
+			shake.add_trauma(impact.trauma);
+			controller.hold_for(impact.hit_stop);
+		}
+	}
+}
+
+/// Takes the shake off, so that the camera stays put even on frames where it doesn't follow
+fn camera_unshake(mut q_camera: Query<(&mut Shake, &mut Transform)>) {
+	if let Ok((mut shake, mut transform)) = q_camera.get_single_mut() {
+		transform.translation -= std::mem::take(&mut shake.offset);
+	}
+}
+
+/// Offsets the camera from where it follows, so this runs after it every frame
+fn camera_shake(
+	time: Res<Time>,
+	settings: Res<MotionSettings>,
+	mut q_camera: Query<(&mut Shake, &mut Transform)>,
+) {
+	let Ok((mut shake, mut transform)) = q_camera.get_single_mut() else {
+		return;
+	};
+	if settings.reduced_motion {
+		shake.trauma = 0.0;
+	}
+	// the screen keeps shaking while the clock is frozen (e.g. during the intro)
+	shake.trauma = (shake.trauma - TRAUMA_DECAY * time.raw_delta_seconds()).max(0.0);
+
+	// squared so that small impacts stay subtle
+	let amount = shake.trauma * shake.trauma;
+	let t = time.raw_elapsed_seconds() * SHAKE_FREQUENCY;
+	shake.offset = Vec3::new(noise(t, 0.0), noise(t, 1.0), 0.0) * MAX_OFFSET * amount;
+	transform.translation += shake.offset;
+	transform.rotation = Quat::from_rotation_z(noise(t, 2.0) * MAX_ANGLE * amount);
+}
+
+/// Smooth pseudo-random value between -1 and 1, different for each seed
+fn noise(t: f32, seed: f32) -> f32 {
+	let t = t + seed * 17.3;
+	(t.sin() + (t * 2.3 + 1.7).sin() * 0.5 + (t * 4.1 + 4.2).sin() * 0.25) / 1.75
+}
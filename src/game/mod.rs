@@ -1,3 +1,9 @@
+pub mod camera;
+pub mod intro;
+pub mod juice;
+pub mod pause;
+pub mod practice;
+
 use std::time::Instant;
 
 use bevy::{
@@ -9,6 +15,11 @@ use bevy_ecs_ldtk::{prelude::LdtkFields, LdtkLevel, LevelSelection, LevelSet, Re
 use bevy_egui::{egui, EguiContexts};
 use bevy_rapier2d::prelude::CollisionEvent;
 
+use self::{
+	camera::CameraController,
+	juice::Shake,
+	practice::{ActiveCheckpoint, PracticeMode, Segment},
+};
 use crate::{
 	input::Action,
 	leaderboard::{CurrentScore, Leaderboard, Nickname, Score},
@@ -16,13 +27,21 @@ use crate::{
 	player::Player,
 	replay::ReplayRecording,
 	states::{AppState, Exit},
+	tas::TasMode,
+	video::GraphicsQuality,
 };
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
 	fn build(&self, app: &mut App) {
-		app.add_event::<Restart>()
+		app.add_plugin(practice::PracticePlugin)
+			.add_plugin(camera::CameraPlugin)
+			.add_plugin(pause::PausePlugin)
+			.add_plugin(intro::IntroPlugin)
+			.add_plugin(juice::JuicePlugin)
+			.add_event::<Restart>()
+			.add_event::<GameplayEvent>()
 			.add_systems((setup,).in_schedule(OnEnter(AppState::Game)))
 			.add_system(exit.in_schedule(OnExit(AppState::Game)))
 			.add_system(back_to_menu)
@@ -32,6 +51,20 @@ impl Plugin for GamePlugin {
 	}
 }
 
+/// Something that happened during a run, which other systems (like audio) react to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameplayEvent {
+	Jumped,
+	GroundPounded,
+	Launched,
+	Teleported,
+	Finished,
+	/// Touched the ground after being in the air
+	Landed,
+	/// Ran some distance on the ground
+	Footstep,
+}
+
 /// When the current run started
 #[derive(Resource)]
 pub struct StartTime(pub Instant);
@@ -45,32 +78,50 @@ fn back_to_menu(mut next_app_state: ResMut<NextState<AppState>>, keys: Res<Input
 
 fn exit(mut _commands: Commands) {}
 
-fn setup(mut commands: Commands) {
-	commands.insert_resource(StartTime(Instant::now()));
+fn setup(
+	mut commands: Commands,
+	active_checkpoint: Res<ActiveCheckpoint>,
+	quality: Res<GraphicsQuality>,
+) {
+	// When restarting from a checkpoint, the timer resumes from where it was
+	let start_time = match &active_checkpoint.0 {
+		Some(checkpoint) => Instant::now().checked_sub(checkpoint.elapsed),
+		None => None,
+	};
+	commands.insert_resource(StartTime(start_time.unwrap_or_else(Instant::now)));
 
 	let mut camera = Camera2dBundle::default();
 	camera.projection.scaling_mode = ScalingMode::FixedVertical(2.0);
-	camera.projection.scale = 2f32.powf(4.0);
+	camera.projection.scale = camera::BASE_SCALE;
 	camera.transform.translation.z -= 100.0;
-	camera.camera.hdr = true;
-	camera.tonemapping = Tonemapping::TonyMcMapface;
-	commands.spawn((
+	camera.camera.hdr = quality.hdr;
+	camera.tonemapping = match quality.tonemapping {
+		true => Tonemapping::TonyMcMapface,
+		false => Tonemapping::None,
+	};
+	let mut camera = commands.spawn((
 		camera,
-		BloomSettings {
-			intensity: 0.05,
-			..default()
-		},
+		CameraController::default(),
+		Shake::default(),
 		ColorGrading {
 			exposure: 1.0,
 			..default()
 		},
 		Exit(AppState::Game),
 	));
+	if quality.bloom {
+		camera.insert(BloomSettings {
+			intensity: 0.05,
+			..default()
+		});
+	}
 }
 
 fn ui(
 	mut egui_ctx: EguiContexts,
 	start_time: Res<StartTime>,
+	practice_mode: Res<PracticeMode>,
+	segment: Res<Segment>,
 	nickname: Res<Nickname>,
 	ldtk_levels: Res<Assets<LdtkLevel>>,
 	q_level: Query<&Handle<LdtkLevel>>,
@@ -83,8 +134,16 @@ fn ui(
 		.anchor(egui::Align2::CENTER_TOP, egui::Vec2::ZERO)
 		.fixed_size(egui::Vec2::new(300.0, 100.0))
 		.show(egui_ctx.ctx_mut(), |ui| {
-			let score = Score(start_time.0.elapsed().as_millis() as u64);
-			ui.label(format!("{score:.2}"));
+			if practice_mode.0 {
+				let score = Score(segment.start.elapsed().as_millis() as u64);
+				ui.label(format!("{score:.2}"));
+				if let Some(last) = segment.last {
+					ui.label(format!("last segment: {last}"));
+				}
+			} else {
+				let score = Score(start_time.0.elapsed().as_millis() as u64);
+				ui.label(format!("{score:.2}"));
+			}
 		});
 
 	// Display the level name
@@ -134,6 +193,9 @@ fn finish(
 	mut next_state: ResMut<NextState<AppState>>,
 	nickname: Res<Nickname>,
 	recording_replay: Res<ReplayRecording>,
+	practice_mode: Res<PracticeMode>,
+	tas_mode: Res<TasMode>,
+	mut ev_gameplay: EventWriter<GameplayEvent>,
 ) {
 	let Ok(player_entity) = q_player.get_single_mut() else {
 		return;
@@ -148,7 +210,9 @@ fn finish(
 					|| (*e1 == player_entity && *e0 == finish_entity)
 				{
 					let score = Score(start_time.0.elapsed().as_millis() as u64);
-					if let LevelSelection::Index(level) = level.as_ref() {
+					// Practice & tool-assisted runs are never submitted
+					let ranked = !practice_mode.0 && !tas_mode.0;
+					if let (LevelSelection::Index(level), true) = (level.as_ref(), ranked) {
 						leaderboard.add_score(
 							*level,
 							&nickname.0,
@@ -157,6 +221,7 @@ fn finish(
 						);
 					}
 					commands.insert_resource(CurrentScore(score));
+					ev_gameplay.send(GameplayEvent::Finished);
 					next_state.set(AppState::Leaderboard);
 				}
 			}
@@ -193,6 +258,7 @@ fn skip(
 	q_ldtk_world: Query<(Entity, &Handle<bevy_ecs_ldtk::LdtkAsset>), With<LevelSet>>,
 	ldtk_asset: Res<Assets<bevy_ecs_ldtk::LdtkAsset>>,
 	mut level_selection: ResMut<LevelSelection>,
+	mut active_checkpoint: ResMut<ActiveCheckpoint>,
 ) {
 	if actions.just_pressed(Action::Skip) {
 		let LevelSelection::Index(level) = level_selection.clone() else {
@@ -204,6 +270,7 @@ fn skip(
 		let nb_levels = ldtk_asset.get(ldtk_handle).unwrap().project.levels.len();
 		commands.entity(world_entity).insert(Respawn);
 		*level_selection = LevelSelection::Index((level + 1) % nb_levels);
+		active_checkpoint.0 = None;
 		next_state.set(AppState::Game);
 	}
 }
@@ -0,0 +1,90 @@
+///
+/// Pausing the run, e.g. when the gamepad gets disconnected
+///
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier2d::prelude::*;
+
+use super::{practice::Segment, StartTime};
+use crate::{
+	input::{self, devices_enabled, Action, InputSet},
+	states::AppState,
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(Paused(false))
+			.add_system(
+				pause_update
+					.in_set(InputSet)
+					.after(input::handle_gamepad_input)
+					.run_if(in_state(AppState::Game))
+					.run_if(devices_enabled),
+			)
+			.add_system(pause_ui.run_if(|paused: Res<Paused>| paused.0))
+			.add_system(pause_exit.in_schedule(OnExit(AppState::Game)));
+	}
+}
+
+/// Whether the run is paused
+#[derive(Resource)]
+pub struct Paused(pub bool);
+
+/// Freezes the clock & physics while paused, and swallows the inputs
+pub fn pause_update(
+	mut paused: ResMut<Paused>,
+	mut actions: ResMut<Input<Action>>,
+	mut time: ResMut<Time>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+	mut start_time: ResMut<StartTime>,
+	mut segment: ResMut<Segment>,
+) {
+	if paused.0 && actions.just_pressed(Action::Jump) {
+		paused.0 = false;
+		// resuming shouldn't make the player jump
+		actions.reset_all();
+	}
+
+	if paused.is_changed() {
+		match paused.0 {
+			true => time.pause(),
+			false => time.unpause(),
+		}
+		rapier_config.physics_pipeline_active = !paused.0;
+	}
+
+	if paused.0 {
+		// the timer doesn't run while paused
+		let delta = time.raw_delta();
+		start_time.0 += delta;
+		segment.start += delta;
+		actions.reset_all();
+	}
+}
+
+fn pause_exit(
+	mut paused: ResMut<Paused>,
+	mut time: ResMut<Time>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+) {
+	if paused.0 {
+		paused.0 = false;
+		time.unpause();
+		rapier_config.physics_pipeline_active = true;
+	}
+}
+
+fn pause_ui(mut egui_ctx: EguiContexts) {
+	egui::Window::new("paused")
+		.movable(false)
+		.collapsible(false)
+		.resizable(false)
+		.title_bar(false)
+		.anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+		.show(egui_ctx.ctx_mut(), |ui| {
+			ui.label("Paused");
+			ui.label("Press Jump to resume");
+		});
+}
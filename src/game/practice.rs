@@ -0,0 +1,115 @@
+///
+/// Practice mode: restarts go back to the last checkpoint, and scores are never submitted
+///
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::StartTime;
+use crate::{
+	leaderboard::Score, level::checkpoint::Checkpoint, player::Player, replay::ReplayRecording,
+	states::AppState,
+};
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(PracticeMode(false))
+			.insert_resource(ActiveCheckpoint(None))
+			.insert_resource(Segment {
+				start: Instant::now(),
+				last: None,
+			})
+			.add_system(segment_start.in_schedule(OnEnter(AppState::Game)))
+			.add_system(clear_checkpoint.in_schedule(OnEnter(AppState::Menu)))
+			.add_system(clear_checkpoint.in_schedule(OnEnter(AppState::Leaderboard)))
+			.add_system(
+				activate_checkpoint
+					.run_if(in_state(AppState::Game))
+					.run_if(|practice_mode: Res<PracticeMode>| practice_mode.0),
+			);
+	}
+}
+
+/// Whether the player is practicing
+#[derive(Resource)]
+pub struct PracticeMode(pub bool);
+
+/// State of the player when they went through the last checkpoint
+#[derive(Clone)]
+pub struct CheckpointState {
+	pub pos: Vec2,
+	pub vel: Vec2,
+	pub elapsed: Duration,
+	/// Fixed timesteps of the run until then, since the recording restarts with the player
+	pub ticks: usize,
+}
+
+/// The checkpoint the player will restart from
+#[derive(Resource)]
+pub struct ActiveCheckpoint(pub Option<CheckpointState>);
+
+impl ActiveCheckpoint {
+	/// Fixed timesteps of the run before the current attempt
+	pub fn ticks(&self) -> usize {
+		self.0.as_ref().map_or(0, |checkpoint| checkpoint.ticks)
+	}
+}
+
+/// Time of the current segment (between two checkpoints)
+#[derive(Resource)]
+pub struct Segment {
+	pub start: Instant,
+	pub last: Option<Score>,
+}
+
+fn segment_start(mut segment: ResMut<Segment>) {
+	segment.start = Instant::now();
+}
+
+fn clear_checkpoint(mut active_checkpoint: ResMut<ActiveCheckpoint>, mut segment: ResMut<Segment>) {
+	active_checkpoint.0 = None;
+	segment.last = None;
+}
+
+fn activate_checkpoint(
+	mut collision_events: EventReader<CollisionEvent>,
+	q_player: Query<(Entity, &Velocity), With<Player>>,
+	q_checkpoint: Query<&Transform, With<Checkpoint>>,
+	start_time: Res<StartTime>,
+	recording: Res<ReplayRecording>,
+	mut active_checkpoint: ResMut<ActiveCheckpoint>,
+	mut segment: ResMut<Segment>,
+) {
+	let Ok((player_entity, player_vel)) = q_player.get_single() else {
+		return;
+	};
+	for collision_event in collision_events.iter() {
+		let CollisionEvent::Started(e0, e1, _) = collision_event else {
+			continue;
+		};
+		if ![*e0, *e1].contains(&player_entity) {
+			continue;
+		}
+		let Some(checkpoint_tr) = [e0, e1].iter().find_map(|e| q_checkpoint.get(**e).ok()) else {
+			continue;
+		};
+		let pos = checkpoint_tr.translation.truncate();
+
+		// Respawning on a checkpoint shouldn't activate it again
+		if matches!(&active_checkpoint.0, Some(c) if c.pos == pos) {
+			continue;
+		}
+
+		active_checkpoint.0 = Some(CheckpointState {
+			pos,
+			vel: player_vel.linvel,
+			elapsed: start_time.0.elapsed(),
+			ticks: active_checkpoint.ticks() + recording.0.ticks(),
+		});
+		segment.last = Some(Score(segment.start.elapsed().as_millis() as u64));
+		segment.start = Instant::now();
+	}
+}
@@ -0,0 +1,114 @@
+///
+/// Picks the gamepad that drives the player, and handles gamepads being plugged & unplugged
+///
+use std::time::{Duration, Instant};
+
+use bevy::{
+	input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+	prelude::*,
+	utils::HashMap,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use super::{InputMapping, ALL_AXES};
+use crate::{game::pause::Paused, states::AppState, tas::TasMode};
+
+/// How long connection notices stay on screen
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// The gamepad that drives the player
+#[derive(Default, Resource)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// Message about a gamepad being connected or disconnected
+#[derive(Default, Resource)]
+pub struct GamepadNotice(Option<(String, Instant)>);
+
+impl GamepadNotice {
+	fn show(&mut self, message: String) {
+		self.0 = Some((message, Instant::now()));
+	}
+}
+
+pub fn gamepad_connections(
+	mut ev_connection: EventReader<GamepadConnectionEvent>,
+	mut names: Local<HashMap<Gamepad, String>>,
+	mut active: ResMut<ActiveGamepad>,
+	mut notice: ResMut<GamepadNotice>,
+	mut paused: ResMut<Paused>,
+	state: Res<State<AppState>>,
+	tas_mode: Res<TasMode>,
+) {
+	for event in ev_connection.iter() {
+		match &event.connection {
+			GamepadConnection::Connected(info) => {
+				names.insert(event.gamepad, info.name.clone());
+				notice.show(format!("Gamepad connected: {}", info.name));
+			}
+			GamepadConnection::Disconnected => {
+				let name = names.remove(&event.gamepad).unwrap_or_default();
+				notice.show(format!("Gamepad disconnected: {name}"));
+				if active.0 == Some(event.gamepad) {
+					active.0 = None;
+					// don't let the player run into a wall while the pad is gone
+					if state.0 == AppState::Game && !tas_mode.0 {
+						paused.0 = true;
+					}
+				}
+			}
+		}
+	}
+}
+
+/// The preferred gamepad if it is connected, otherwise the last one that was used
+pub fn select_gamepad(
+	mut active: ResMut<ActiveGamepad>,
+	mapping: Res<InputMapping>,
+	gamepads: Res<Gamepads>,
+	buttons: Res<Input<GamepadButton>>,
+	axes: Res<Axis<GamepadAxis>>,
+) {
+	let preferred = gamepads.iter().find(|gp| {
+		mapping.preferred_gamepad.is_some()
+			&& gamepads.name(*gp) == mapping.preferred_gamepad.as_deref()
+	});
+	if let Some(gp) = preferred {
+		if active.0 != Some(gp) {
+			active.0 = Some(gp);
+		}
+		return;
+	}
+
+	let tilted = |gp: Gamepad| {
+		ALL_AXES.iter().any(|axis_type| {
+			let value = axes.get(GamepadAxis::new(gp, *axis_type)).unwrap_or(0.0);
+			mapping.sticks.apply_deadzone(value).abs() > mapping.sticks.press_threshold
+		})
+	};
+	let used = (buttons.get_just_pressed().map(|b| b.gamepad))
+		.chain(gamepads.iter().filter(|gp| tilted(*gp)))
+		.next();
+	if let Some(gp) = used {
+		if active.0 != Some(gp) {
+			active.0 = Some(gp);
+		}
+	}
+}
+
+pub fn gamepad_notice_ui(mut egui_ctx: EguiContexts, notice: Res<GamepadNotice>) {
+	let Some((message, shown_at)) = &notice.0 else {
+		return;
+	};
+	if shown_at.elapsed() > NOTICE_DURATION {
+		return;
+	}
+	egui::Window::new("gamepad-notice")
+		.movable(false)
+		.collapsible(false)
+		.resizable(false)
+		.title_bar(false)
+		.anchor(egui::Align2::LEFT_TOP, egui::Vec2::new(16.0, 16.0))
+		.show(egui_ctx.ctx_mut(), |ui| {
+			ui.label(message);
+		});
+}
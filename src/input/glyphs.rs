@@ -0,0 +1,144 @@
+///
+/// Human-readable names of keys & gamepad buttons, as printed on the device
+///
+use bevy::prelude::*;
+
+use super::{Binding, ButtonOrAxis};
+
+/// Controller families label their buttons differently
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerFamily {
+	Xbox,
+	PlayStation,
+	Nintendo,
+}
+
+impl ControllerFamily {
+	/// Guess the family from the name reported by the device
+	/// Unknown controllers usually follow the Xbox layout
+	pub fn from_name(name: &str) -> Self {
+		let name = name.to_lowercase();
+		if [
+			"playstation",
+			"dualshock",
+			"dualsense",
+			"sony",
+			"ps3",
+			"ps4",
+			"ps5",
+		]
+		.iter()
+		.any(|s| name.contains(s))
+		{
+			ControllerFamily::PlayStation
+		} else if ["nintendo", "switch", "joy-con", "pro controller"]
+			.iter()
+			.any(|s| name.contains(s))
+		{
+			ControllerFamily::Nintendo
+		} else {
+			ControllerFamily::Xbox
+		}
+	}
+}
+
+pub fn key_name(key: KeyCode) -> String {
+	let name = match key {
+		KeyCode::Up => "Up Arrow",
+		KeyCode::Down => "Down Arrow",
+		KeyCode::Left => "Left Arrow",
+		KeyCode::Right => "Right Arrow",
+		KeyCode::Return => "Enter",
+		KeyCode::Back => "Backspace",
+		KeyCode::LShift => "Left Shift",
+		KeyCode::RShift => "Right Shift",
+		KeyCode::LControl => "Left Ctrl",
+		KeyCode::RControl => "Right Ctrl",
+		KeyCode::LAlt => "Left Alt",
+		KeyCode::RAlt => "Right Alt",
+		KeyCode::Key0 => "0",
+		KeyCode::Key1 => "1",
+		KeyCode::Key2 => "2",
+		KeyCode::Key3 => "3",
+		KeyCode::Key4 => "4",
+		KeyCode::Key5 => "5",
+		KeyCode::Key6 => "6",
+		KeyCode::Key7 => "7",
+		KeyCode::Key8 => "8",
+		KeyCode::Key9 => "9",
+		_ => return format!("{key:?}"),
+	};
+	name.to_owned()
+}
+
+pub fn button_name(button: GamepadButtonType, family: ControllerFamily) -> String {
+	use ControllerFamily::*;
+	use GamepadButtonType::*;
+
+	let name = match (button, family) {
+		(South, Xbox) => "A",
+		(East, Xbox) => "B",
+		(West, Xbox) => "X",
+		(North, Xbox) => "Y",
+		(South, PlayStation) => "Cross",
+		(East, PlayStation) => "Circle",
+		(West, PlayStation) => "Square",
+		(North, PlayStation) => "Triangle",
+		// Nintendo swaps the labels of A/B and X/Y
+		(South, Nintendo) => "B",
+		(East, Nintendo) => "A",
+		(West, Nintendo) => "Y",
+		(North, Nintendo) => "X",
+		(LeftTrigger, Xbox) => "LB",
+		(RightTrigger, Xbox) => "RB",
+		(LeftTrigger2, Xbox) => "LT",
+		(RightTrigger2, Xbox) => "RT",
+		(LeftTrigger, PlayStation) => "L1",
+		(RightTrigger, PlayStation) => "R1",
+		(LeftTrigger2, PlayStation) => "L2",
+		(RightTrigger2, PlayStation) => "R2",
+		(LeftTrigger, Nintendo) => "L",
+		(RightTrigger, Nintendo) => "R",
+		(LeftTrigger2, Nintendo) => "ZL",
+		(RightTrigger2, Nintendo) => "ZR",
+		(Select, Xbox) => "View",
+		(Start, Xbox) => "Menu",
+		(Select, PlayStation) => "Share",
+		(Start, PlayStation) => "Options",
+		(Select, Nintendo) => "-",
+		(Start, Nintendo) => "+",
+		(LeftThumb, PlayStation) => "L3",
+		(RightThumb, PlayStation) => "R3",
+		(LeftThumb, _) => "Left Stick Click",
+		(RightThumb, _) => "Right Stick Click",
+		(DPadUp, _) => "D-Pad Up",
+		(DPadDown, _) => "D-Pad Down",
+		(DPadLeft, _) => "D-Pad Left",
+		(DPadRight, _) => "D-Pad Right",
+		_ => return format!("{button:?}"),
+	};
+	name.to_owned()
+}
+
+pub fn axis_name(axis: GamepadAxisType, negative: bool) -> String {
+	let name = match (axis, negative) {
+		(GamepadAxisType::LeftStickX, true) => "Left Stick Left",
+		(GamepadAxisType::LeftStickX, false) => "Left Stick Right",
+		(GamepadAxisType::LeftStickY, true) => "Left Stick Down",
+		(GamepadAxisType::LeftStickY, false) => "Left Stick Up",
+		(GamepadAxisType::RightStickX, true) => "Right Stick Left",
+		(GamepadAxisType::RightStickX, false) => "Right Stick Right",
+		(GamepadAxisType::RightStickY, true) => "Right Stick Down",
+		(GamepadAxisType::RightStickY, false) => "Right Stick Up",
+		_ => return ButtonOrAxis::Axis(axis, negative).to_string(),
+	};
+	name.to_owned()
+}
+
+pub fn binding_name(binding: Binding, family: ControllerFamily) -> String {
+	match binding {
+		Binding::Key(key) => key_name(key),
+		Binding::Gamepad(ButtonOrAxis::Button(button)) => button_name(button, family),
+		Binding::Gamepad(ButtonOrAxis::Axis(axis, negative)) => axis_name(axis, negative),
+	}
+}
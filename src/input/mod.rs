@@ -0,0 +1,689 @@
+pub mod gamepad;
+pub mod glyphs;
+
+use std::collections::BTreeMap;
+
+use bevy::{
+	prelude::*,
+	utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use self::gamepad::{ActiveGamepad, GamepadNotice};
+use crate::{
+	states::AppState,
+	tas::{tas_running, TasMode},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, SystemSet)]
+pub struct InputSet;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(Input::<Action>::default())
+			.insert_resource(AnalogActions::default())
+			.insert_resource(CurrentInputMode::Keyboard)
+			.insert_resource(ActiveGamepad::default())
+			.insert_resource(GamepadNotice::default())
+			.add_systems(
+				(gamepad::gamepad_connections, gamepad::select_gamepad)
+					.chain()
+					.in_set(InputSet)
+					.before(handle_keyboard_input),
+			)
+			.add_systems(
+				(handle_keyboard_input, handle_gamepad_input)
+					.chain()
+					.in_set(InputSet)
+					.distributive_run_if(devices_enabled),
+			)
+			.add_system(gamepad::gamepad_notice_ui);
+	}
+}
+
+/// In TAS mode, inputs come from the timeline instead
+pub fn devices_enabled(tas_mode: Res<TasMode>, state: Res<State<AppState>>) -> bool {
+	!tas_running(tas_mode, state)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "ButtonOrAxisFormat", into = "ButtonOrAxisFormat")]
+pub enum ButtonOrAxis {
+	Button(GamepadButtonType),
+	Axis(GamepadAxisType, bool), // false: positive, true: negative
+}
+
+/// TOML can't represent tuple variants, so the axis & its direction are written as an array
+/// This reads JSON settings files the same as the derived format
+#[derive(Serialize, Deserialize)]
+enum ButtonOrAxisFormat {
+	Button(GamepadButtonType),
+	Axis((GamepadAxisType, bool)),
+}
+
+impl From<ButtonOrAxisFormat> for ButtonOrAxis {
+	fn from(format: ButtonOrAxisFormat) -> Self {
+		match format {
+			ButtonOrAxisFormat::Button(button) => Self::Button(button),
+			ButtonOrAxisFormat::Axis((axis, negative)) => Self::Axis(axis, negative),
+		}
+	}
+}
+
+impl From<ButtonOrAxis> for ButtonOrAxisFormat {
+	fn from(button_or_axis: ButtonOrAxis) -> Self {
+		match button_or_axis {
+			ButtonOrAxis::Button(button) => Self::Button(button),
+			ButtonOrAxis::Axis(axis, negative) => Self::Axis((axis, negative)),
+		}
+	}
+}
+
+impl std::fmt::Display for ButtonOrAxis {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ButtonOrAxis::Button(btn) => write!(f, "{btn:?}"),
+			ButtonOrAxis::Axis(axis, neg) => {
+				if *neg {
+					write!(f, "<< {axis:?}")
+				} else {
+					write!(f, "{axis:?} >>")
+				}
+			}
+		}
+	}
+}
+
+/// Bindings of an action, any of them triggers it
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "MappingFormat")]
+pub struct Mapping {
+	pub keys: Vec<KeyCode>,
+	pub buttons: Vec<ButtonOrAxis>,
+}
+
+/// Formats of mappings found in settings files
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MappingFormat {
+	Current {
+		keys: Vec<KeyCode>,
+		buttons: Vec<ButtonOrAxis>,
+	},
+	/// Before actions could have multiple bindings
+	Single {
+		key: KeyCode,
+		button_or_axis: ButtonOrAxis,
+	},
+}
+
+impl From<MappingFormat> for Mapping {
+	fn from(format: MappingFormat) -> Self {
+		match format {
+			MappingFormat::Current { keys, buttons } => Self { keys, buttons },
+			MappingFormat::Single {
+				key,
+				button_or_axis,
+			} => Self {
+				keys: vec![key],
+				buttons: vec![button_or_axis],
+			},
+		}
+	}
+}
+
+/// Something an action can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+	Key(KeyCode),
+	Gamepad(ButtonOrAxis),
+}
+
+impl std::fmt::Display for Binding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Binding::Key(key) => write!(f, "{key:?}"),
+			Binding::Gamepad(button_or_axis) => write!(f, "{button_or_axis}"),
+		}
+	}
+}
+
+/// How analog sticks are turned into actions
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickConfig {
+	/// Stick values below this are ignored
+	pub deadzone: f32,
+	/// An action bound to an axis gets pressed above this value...
+	pub press_threshold: f32,
+	/// ...and released below this one
+	pub release_threshold: f32,
+	/// Whether horizontal movement follows how much the stick is tilted
+	pub analog_movement: bool,
+}
+
+impl Default for StickConfig {
+	fn default() -> Self {
+		Self {
+			deadzone: 0.15,
+			press_threshold: 0.8,
+			release_threshold: 0.6,
+			analog_movement: false,
+		}
+	}
+}
+
+impl StickConfig {
+	/// Rescales a stick value so that it starts from 0 at the edge of the deadzone
+	pub fn apply_deadzone(&self, value: f32) -> f32 {
+		if value.abs() <= self.deadzone {
+			0.0
+		} else {
+			value.signum() * (value.abs() - self.deadzone) / (1.0 - self.deadzone).max(f32::EPSILON)
+		}
+	}
+}
+
+/// Gamepad bindings of each action
+pub type GamepadBindings = BTreeMap<Action, Vec<ButtonOrAxis>>;
+
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(from = "InputMappingFormat")]
+pub struct InputMapping {
+	pub actions: BTreeMap<Action, Mapping>,
+	pub sticks: StickConfig,
+	/// Gamepad bindings customized for a specific device, by device name
+	pub devices: BTreeMap<String, GamepadBindings>,
+	/// Name of the gamepad that drives the player, the last one used if unset
+	pub preferred_gamepad: Option<String>,
+}
+
+/// Formats of input mappings found in settings files
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InputMappingFormat {
+	/// Before sticks could be configured
+	/// Tried first, as every field of the current format is optional
+	ActionsOnly(BTreeMap<Action, Mapping>),
+	Current {
+		#[serde(default)]
+		actions: BTreeMap<Action, Mapping>,
+		#[serde(default)]
+		sticks: StickConfig,
+		#[serde(default)]
+		devices: BTreeMap<String, GamepadBindings>,
+		#[serde(default)]
+		preferred_gamepad: Option<String>,
+	},
+}
+
+impl From<InputMappingFormat> for InputMapping {
+	fn from(format: InputMappingFormat) -> Self {
+		let mut mapping = match format {
+			InputMappingFormat::Current {
+				actions,
+				sticks,
+				devices,
+				preferred_gamepad,
+			} => Self {
+				actions,
+				sticks,
+				devices,
+				preferred_gamepad,
+			},
+			InputMappingFormat::ActionsOnly(actions) => Self {
+				actions,
+				..default()
+			},
+		};
+		// actions added since the file was written get their default bindings
+		for (action, default_mapping) in InputMapping::default().actions {
+			mapping.actions.entry(action).or_insert(default_mapping);
+		}
+		mapping
+	}
+}
+
+impl InputMapping {
+	/// Whether one of the keys of an action was just pressed, for when actions aren't read from devices
+	pub fn key_just_pressed(&self, action: Action, keys: &Input<KeyCode>) -> bool {
+		keys.any_just_pressed(self.actions[&action].keys.iter().copied())
+	}
+
+	/// Gamepad bindings of an action on the given device
+	pub fn buttons(&self, action: Action, device: Option<&str>) -> &[ButtonOrAxis] {
+		match device.and_then(|d| self.devices.get(d)?.get(&action)) {
+			Some(buttons) => buttons,
+			None => &self.actions[&action].buttons,
+		}
+	}
+
+	/// Gamepad bindings of an action on the given device, for editing
+	/// A device gets its own bindings the first time they are edited
+	pub fn buttons_mut(&mut self, action: Action, device: Option<&str>) -> &mut Vec<ButtonOrAxis> {
+		let shared = &mut self.actions.get_mut(&action).unwrap().buttons;
+		match device {
+			Some(device) => self
+				.devices
+				.entry(device.to_owned())
+				.or_default()
+				.entry(action)
+				.or_insert_with(|| shared.clone()),
+			None => shared,
+		}
+	}
+
+	/// Everything that triggers an action on the given device
+	pub fn bindings(
+		&self,
+		action: Action,
+		device: Option<&str>,
+	) -> impl Iterator<Item = Binding> + '_ {
+		let keys = self.actions[&action].keys.iter().map(|k| Binding::Key(*k));
+		keys.chain(
+			self.buttons(action, device)
+				.iter()
+				.map(|b| Binding::Gamepad(*b)),
+		)
+	}
+
+	/// Bindings that trigger more than one action on the given device
+	/// Menu & gameplay actions never happen at the same time, so they can share bindings
+	pub fn conflicts(&self, device: Option<&str>) -> Vec<(Binding, Vec<Action>)> {
+		let mut bound: Vec<(Binding, Vec<Action>)> = vec![];
+		for action in self.actions.keys() {
+			for binding in self.bindings(*action, device) {
+				let conflicting = |(b, actions): &&mut (Binding, Vec<Action>)| {
+					*b == binding && actions[0].is_menu() == action.is_menu()
+				};
+				match bound.iter_mut().find(conflicting) {
+					Some((_, actions)) if !actions.contains(action) => actions.push(*action),
+					Some(_) => {}
+					None => bound.push((binding, vec![*action])),
+				}
+			}
+		}
+		bound.retain(|(_, actions)| actions.len() > 1);
+		bound
+	}
+}
+
+impl Default for InputMapping {
+	fn default() -> Self {
+		Self {
+			actions: BTreeMap::from([
+				(
+					Action::Jump,
+					Mapping {
+						keys: vec![KeyCode::Space, KeyCode::Up],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::South)],
+					},
+				),
+				(
+					Action::Left,
+					Mapping {
+						keys: vec![KeyCode::Left, KeyCode::A],
+						buttons: vec![
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickX, true),
+							ButtonOrAxis::Button(GamepadButtonType::DPadLeft),
+						],
+					},
+				),
+				(
+					Action::Right,
+					Mapping {
+						keys: vec![KeyCode::Right, KeyCode::D],
+						buttons: vec![
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickX, false),
+							ButtonOrAxis::Button(GamepadButtonType::DPadRight),
+						],
+					},
+				),
+				(
+					Action::GroundPound,
+					Mapping {
+						keys: vec![KeyCode::Down, KeyCode::S],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::West)],
+					},
+				),
+				(
+					Action::Restart,
+					Mapping {
+						keys: vec![KeyCode::R],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::North)],
+					},
+				),
+				(
+					Action::SaveState,
+					Mapping {
+						keys: vec![KeyCode::F5],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::LeftThumb)],
+					},
+				),
+				(
+					Action::LoadState,
+					Mapping {
+						keys: vec![KeyCode::F9],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::RightThumb)],
+					},
+				),
+				(
+					Action::MenuUp,
+					Mapping {
+						keys: vec![KeyCode::Up],
+						buttons: vec![
+							ButtonOrAxis::Button(GamepadButtonType::DPadUp),
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickY, false),
+						],
+					},
+				),
+				(
+					Action::MenuDown,
+					Mapping {
+						keys: vec![KeyCode::Down],
+						buttons: vec![
+							ButtonOrAxis::Button(GamepadButtonType::DPadDown),
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickY, true),
+						],
+					},
+				),
+				(
+					Action::MenuLeft,
+					Mapping {
+						keys: vec![KeyCode::Left],
+						buttons: vec![
+							ButtonOrAxis::Button(GamepadButtonType::DPadLeft),
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickX, true),
+						],
+					},
+				),
+				(
+					Action::MenuRight,
+					Mapping {
+						keys: vec![KeyCode::Right],
+						buttons: vec![
+							ButtonOrAxis::Button(GamepadButtonType::DPadRight),
+							ButtonOrAxis::Axis(GamepadAxisType::LeftStickX, false),
+						],
+					},
+				),
+				(
+					Action::Confirm,
+					Mapping {
+						keys: vec![KeyCode::Return],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::South)],
+					},
+				),
+				(
+					Action::Back,
+					Mapping {
+						keys: vec![KeyCode::Escape],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::East)],
+					},
+				),
+				(
+					Action::TasAdvance,
+					Mapping {
+						keys: vec![KeyCode::Period],
+						buttons: vec![],
+					},
+				),
+				(
+					Action::TasPlay,
+					Mapping {
+						keys: vec![KeyCode::P],
+						buttons: vec![],
+					},
+				),
+				(
+					Action::TasRewind,
+					Mapping {
+						keys: vec![KeyCode::Home],
+						buttons: vec![],
+					},
+				),
+				#[cfg(debug_assertions)]
+				(
+					Action::Skip,
+					Mapping {
+						keys: vec![KeyCode::N],
+						buttons: vec![ButtonOrAxis::Button(GamepadButtonType::LeftTrigger)],
+					},
+				),
+			]),
+			sticks: StickConfig::default(),
+			devices: BTreeMap::new(),
+			preferred_gamepad: None,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Resource)]
+pub enum CurrentInputMode {
+	Keyboard,
+	Gamepad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+	Jump,
+	Left,
+	Right,
+	GroundPound,
+	Restart,
+	SaveState,
+	LoadState,
+	// Menu navigation
+	MenuUp,
+	MenuDown,
+	MenuLeft,
+	MenuRight,
+	Confirm,
+	Back,
+	// TAS tools, read from the keyboard while the timeline drives the player
+	TasAdvance,
+	TasPlay,
+	TasRewind,
+	#[cfg(debug_assertions)]
+	Skip,
+}
+
+/// Actions that drive the player, recorded in replays
+pub const PLAYER_ACTIONS: [Action; 4] = [
+	Action::Left,
+	Action::Right,
+	Action::Jump,
+	Action::GroundPound,
+];
+
+impl Action {
+	/// Parses the name of an action in level texts, e.g. `<ground_pound>`
+	pub fn from_token(token: &str) -> Option<Action> {
+		let action = match token {
+			"jump" => Action::Jump,
+			"left" => Action::Left,
+			"right" => Action::Right,
+			"ground_pound" => Action::GroundPound,
+			"restart" => Action::Restart,
+			"save_state" => Action::SaveState,
+			"load_state" => Action::LoadState,
+			"menu_up" => Action::MenuUp,
+			"menu_down" => Action::MenuDown,
+			"menu_left" => Action::MenuLeft,
+			"menu_right" => Action::MenuRight,
+			"confirm" => Action::Confirm,
+			"back" => Action::Back,
+			"tas_advance" => Action::TasAdvance,
+			"tas_play" => Action::TasPlay,
+			"tas_rewind" => Action::TasRewind,
+			#[cfg(debug_assertions)]
+			"skip" => Action::Skip,
+			_ => return None,
+		};
+		Some(action)
+	}
+
+	/// Whether the action is only used to navigate menus
+	pub fn is_menu(&self) -> bool {
+		matches!(
+			self,
+			Action::MenuUp
+				| Action::MenuDown
+				| Action::MenuLeft
+				| Action::MenuRight
+				| Action::Confirm
+				| Action::Back
+		)
+	}
+}
+
+/// How far the stick is tilted towards actions bound to an axis, between 0 and 1
+/// Only filled when analog movement is enabled
+#[derive(Default, Resource)]
+pub struct AnalogActions(pub HashMap<Action, f32>);
+
+impl AnalogActions {
+	/// How strongly an action is held: the stick value if it is tilted, otherwise 0 or 1
+	pub fn strength(&self, actions: &Input<Action>, action: Action) -> f32 {
+		match self.0.get(&action) {
+			Some(value) if *value > 0.0 => *value,
+			_ if actions.pressed(action) => 1.0,
+			_ => 0.0,
+		}
+	}
+}
+
+pub fn handle_keyboard_input(
+	mut actions: ResMut<Input<Action>>,
+	mut analog: ResMut<AnalogActions>,
+	mapping: Res<InputMapping>,
+	keys: Res<Input<KeyCode>>,
+	mut input_mode: ResMut<CurrentInputMode>,
+) {
+	actions.clear();
+	analog.0.clear();
+	for (action, mapping) in &mapping.actions {
+		if keys.any_just_pressed(mapping.keys.iter().copied()) {
+			*input_mode = CurrentInputMode::Keyboard;
+			actions.press(*action);
+		}
+		// the action stays pressed as long as one of its keys is held
+		if keys.any_just_released(mapping.keys.iter().copied())
+			&& !keys.any_pressed(mapping.keys.iter().copied())
+		{
+			actions.release(*action);
+		}
+	}
+}
+
+/// Axes that can be bound to actions
+pub const ALL_AXES: [GamepadAxisType; 6] = [
+	GamepadAxisType::LeftStickX,
+	GamepadAxisType::LeftStickY,
+	GamepadAxisType::LeftZ,
+	GamepadAxisType::RightStickX,
+	GamepadAxisType::RightStickY,
+	GamepadAxisType::RightZ,
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct GamepadAxes(HashMap<GamepadAxisType, f32>);
+
+/// Stick directions that are currently held (an axis and whether it's negative), for each action
+#[derive(Clone, Debug, Default)]
+pub struct HeldAxes(HashSet<(Action, GamepadAxisType, bool)>);
+
+pub fn handle_gamepad_input(
+	mut actions: ResMut<Input<Action>>,
+	mut analog: ResMut<AnalogActions>,
+	mapping: Res<InputMapping>,
+	gamepad_axes: Res<Axis<GamepadAxis>>,
+	buttons: Res<Input<GamepadButton>>,
+	gamepads: Res<Gamepads>,
+	active_gamepad: Res<ActiveGamepad>,
+	mut held_axes: Local<HeldAxes>,
+	mut previous: Local<Option<(Gamepad, Option<String>)>>,
+	mut input_mode: ResMut<CurrentInputMode>,
+) {
+	let current = active_gamepad.0.filter(|gp| gamepads.contains(*gp));
+	// actions held on a gamepad that was switched or disconnected won't get released otherwise
+	if previous.as_ref().map(|(gp, _)| *gp) != current {
+		if let Some((_, device)) = previous.take() {
+			for (action, ..) in held_axes.0.drain() {
+				actions.release(action);
+			}
+			for action in mapping.actions.keys() {
+				let bindings = mapping.buttons(*action, device.as_deref());
+				if bindings
+					.iter()
+					.any(|b| matches!(b, ButtonOrAxis::Button(_)))
+				{
+					actions.release(*action);
+				}
+			}
+		}
+		*previous = current.map(|gp| (gp, gamepads.name(gp).map(str::to_owned)));
+	}
+	let Some(gp) = current else {
+		return;
+	};
+	let device = gamepads.name(gp);
+
+	let mut axes = GamepadAxes::default();
+	for axis in &ALL_AXES {
+		// the gamepad may have been disconnected during this frame
+		if let Some(value) = gamepad_axes.get(GamepadAxis::new(gp, *axis)) {
+			axes.0.insert(*axis, value);
+		}
+	}
+
+	let sticks = &mapping.sticks;
+	for action in mapping.actions.keys() {
+		let bindings = mapping.buttons(*action, device);
+		let gamepad_buttons = bindings.iter().filter_map(|b| match b {
+			ButtonOrAxis::Button(button_type) => Some(GamepadButton {
+				gamepad: gp,
+				button_type: *button_type,
+			}),
+			ButtonOrAxis::Axis(..) => None,
+		});
+		if buttons.any_just_pressed(gamepad_buttons.clone()) {
+			*input_mode = CurrentInputMode::Gamepad;
+			actions.press(*action);
+		}
+		if buttons.any_just_released(gamepad_buttons.clone())
+			&& !buttons.any_pressed(gamepad_buttons)
+		{
+			actions.release(*action);
+		}
+
+		for button_or_axis in bindings {
+			let ButtonOrAxis::Axis(axis, negative) = *button_or_axis else {
+				continue;
+			};
+			let Some(value) = axes.0.get(&axis) else {
+				continue;
+			};
+			// value along the direction of the mapping
+			let value = sticks.apply_deadzone(if negative { -*value } else { *value });
+
+			// different thresholds for pressing & releasing, so that the action doesn't flicker
+			let held = held_axes.0.contains(&(*action, axis, negative));
+			if !held && value > sticks.press_threshold {
+				held_axes.0.insert((*action, axis, negative));
+				actions.press(*action);
+			}
+			if held && value < sticks.release_threshold {
+				held_axes.0.remove(&(*action, axis, negative));
+				actions.release(*action);
+			}
+
+			if sticks.analog_movement {
+				let strength = analog.0.entry(*action).or_default();
+				*strength = strength.max(value);
+			}
+		}
+	}
+}
@@ -0,0 +1,136 @@
+///
+/// Overlay showing which actions are held, by the player and by their ghost
+///
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	game::StartTime,
+	input::Action,
+	replay::{Ghost, InputFrame},
+	states::AppState,
+};
+
+pub struct InputDisplayPlugin;
+
+impl Plugin for InputDisplayPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(
+			input_display_ui
+				.run_if(in_state(AppState::Game))
+				.run_if(|settings: Res<InputDisplay>| settings.enabled),
+		);
+	}
+}
+
+/// Corner of the screen the overlay is shown in
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Corner {
+	TopLeft,
+	TopRight,
+	#[default]
+	BottomLeft,
+	BottomRight,
+}
+
+impl Corner {
+	pub fn next(self) -> Self {
+		match self {
+			Corner::TopLeft => Corner::TopRight,
+			Corner::TopRight => Corner::BottomRight,
+			Corner::BottomRight => Corner::BottomLeft,
+			Corner::BottomLeft => Corner::TopLeft,
+		}
+	}
+
+	fn anchor(self) -> (egui::Align2, egui::Vec2) {
+		match self {
+			Corner::TopLeft => (egui::Align2::LEFT_TOP, egui::Vec2::new(16.0, 16.0)),
+			Corner::TopRight => (egui::Align2::RIGHT_TOP, egui::Vec2::new(-16.0, 16.0)),
+			Corner::BottomLeft => (egui::Align2::LEFT_BOTTOM, egui::Vec2::new(16.0, -16.0)),
+			Corner::BottomRight => (egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-16.0, -16.0)),
+		}
+	}
+}
+
+impl std::fmt::Display for Corner {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Corner::TopLeft => write!(f, "top left"),
+			Corner::TopRight => write!(f, "top right"),
+			Corner::BottomLeft => write!(f, "bottom left"),
+			Corner::BottomRight => write!(f, "bottom right"),
+		}
+	}
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct InputDisplay {
+	pub enabled: bool,
+	pub corner: Corner,
+}
+
+/// Draws held actions as lit keys, laid out like arrow keys
+fn input_pad(ui: &mut egui::Ui, label: &str, frame: &InputFrame) {
+	let key = |ui: &mut egui::Ui, name: &str, action: Action| {
+		let color = match frame.0.contains(&action) {
+			true => egui::Color32::from_rgb(230, 60, 60),
+			false => egui::Color32::from_gray(40),
+		};
+		ui.add(
+			egui::Button::new(name)
+				.fill(color)
+				.min_size(egui::Vec2::new(64.0, 32.0)),
+		);
+	};
+
+	ui.vertical(|ui| {
+		ui.label(label);
+		egui::Grid::new(label)
+			.spacing(egui::Vec2::splat(2.0))
+			.show(ui, |ui| {
+				ui.label("");
+				key(ui, "Jump", Action::Jump);
+				ui.end_row();
+				key(ui, "Left", Action::Left);
+				key(ui, "Pound", Action::GroundPound);
+				key(ui, "Right", Action::Right);
+				ui.end_row();
+			});
+	});
+}
+
+fn input_display_ui(
+	mut egui_ctx: EguiContexts,
+	settings: Res<InputDisplay>,
+	actions: Res<Input<Action>>,
+	start_time: Res<StartTime>,
+	q_ghost: Query<&Ghost>,
+) {
+	// Ghosts recorded before inputs were saved only have positions
+	let ghost_frame = q_ghost.get_single().ok().and_then(|ghost| {
+		let tick = Ghost::tick(&start_time) as usize;
+		ghost
+			.0
+			.inputs
+			.get(tick.min(ghost.0.inputs.len().checked_sub(1)?))
+	});
+
+	let (align, offset) = settings.corner.anchor();
+	egui::Window::new("input-display")
+		.movable(false)
+		.collapsible(false)
+		.resizable(false)
+		.title_bar(false)
+		.anchor(align, offset)
+		.show(egui_ctx.ctx_mut(), |ui| {
+			ui.horizontal(|ui| {
+				input_pad(ui, "You", &InputFrame::capture(&actions));
+				if let Some(frame) = ghost_frame {
+					input_pad(ui, "Ghost", frame);
+				}
+			});
+		});
+}
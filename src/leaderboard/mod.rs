@@ -7,6 +7,7 @@ use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+	menu::focus::{focus_navigation, MenuFocus},
 	replay::{Replay, ReplayData},
 	states::{AppState, Exit},
 };
@@ -19,12 +20,15 @@ impl Plugin for LeaderboardPlugin {
 	fn build(&self, app: &mut App) {
 		app.insert_resource(Leaderboard::load())
 			.add_event::<ui::UiMessage>()
+			.insert_resource(MenuFocus::<ui::UiMessage>::default())
 			.add_system(setup.in_schedule(OnEnter(AppState::Leaderboard)))
+			.add_system(ui::focus_next.in_schedule(OnEnter(AppState::Leaderboard)))
 			.add_system(exit.in_schedule(OnExit(AppState::Leaderboard)))
 			.add_systems(
 				(
 					ui::leaderboard_shortcuts,
 					ui::leaderboard_ui,
+					focus_navigation::<ui::UiMessage>.after(ui::leaderboard_ui),
 					ui::leaderboard_update,
 				)
 					.distributive_run_if(in_state(AppState::Leaderboard)),
@@ -96,6 +100,14 @@ impl Leaderboard {
 		scores.sort_by_key(|a| a.1);
 		scores
 	}
+
+	/// Replay of the best run on the level, by anyone
+	pub fn get_best(&self, level: usize) -> Option<&ReplayData> {
+		self.0[level]
+			.values()
+			.min_by_key(|(score, _)| *score)
+			.map(|(_, replay)| replay)
+	}
 }
 
 /// Score for the current finished run
@@ -10,7 +10,10 @@ use bevy_iced::{
 };
 
 use super::{CurrentScore, Leaderboard, Nickname};
-use crate::{input::Action, states::AppState};
+use crate::{
+	game::practice::PracticeMode, input::Action, menu::focus::MenuFocus, states::AppState,
+	tas::TasMode,
+};
 
 #[derive(Clone)]
 pub enum UiMessage {
@@ -18,8 +21,14 @@ pub enum UiMessage {
 	LevelRestart,
 }
 
+/// Going to the next level is the default
+pub fn focus_next(mut focus: ResMut<MenuFocus<UiMessage>>) {
+	focus.focused = (0, 1);
+}
+
 pub fn leaderboard_shortcuts(actions: Res<Input<Action>>, mut messages: EventWriter<UiMessage>) {
-	if actions.just_pressed(Action::Jump) {
+	// Confirm already activates the focused button
+	if actions.just_pressed(Action::Jump) && !actions.just_pressed(Action::Confirm) {
 		messages.send(UiMessage::LevelNext);
 	} else if actions.just_pressed(Action::Restart) {
 		messages.send(UiMessage::LevelRestart);
@@ -32,6 +41,9 @@ pub fn leaderboard_ui(
 	leaderboard: Res<Leaderboard>,
 	level_selection: Res<LevelSelection>,
 	nickname: Res<Nickname>,
+	practice_mode: Res<PracticeMode>,
+	tas_mode: Res<TasMode>,
+	mut focus: ResMut<MenuFocus<UiMessage>>,
 ) {
 	let LevelSelection::Index(level) = level_selection.clone() else {
 		panic!("expected level index");
@@ -54,14 +66,17 @@ pub fn leaderboard_ui(
 		col
 	};
 
+	focus.row();
 	let button_row = Row::new()
 		.spacing(8.0)
-		.push(Button::new("Restart").on_press(UiMessage::LevelRestart))
-		.push(Button::new("Next").on_press(UiMessage::LevelNext));
-
-	let msg = match improved {
-		true => "New best time!",
-		false => "Well done!",
+		.push(focus.button(Button::new("Restart"), UiMessage::LevelRestart))
+		.push(focus.button(Button::new("Next"), UiMessage::LevelNext));
+
+	let msg = match (practice_mode.0, tas_mode.0, improved) {
+		(true, _, _) => "Practice run",
+		(_, true, _) => "Tool-assisted run",
+		(false, false, true) => "New best time!",
+		(false, false, false) => "Well done!",
 	};
 
 	let mut main = Column::new()
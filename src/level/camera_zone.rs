@@ -0,0 +1,73 @@
+///
+/// Areas where the camera zooms, shifts or stops following the player along an axis
+///
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::ldtk_fields::LdtkFields, prelude::*};
+
+use super::LevelSize;
+use crate::states::{AppState, Exit};
+
+/// Axis along which the camera stays centered on the zone
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LockedAxis {
+	X,
+	Y,
+}
+
+#[derive(Component)]
+pub struct CameraZone {
+	/// Area of the zone, in world coordinates
+	pub rect: Rect,
+	/// Above 1 shows less of the level, below 1 shows more
+	pub zoom: f32,
+	pub offset: Vec2,
+	pub locked_axis: Option<LockedAxis>,
+}
+
+pub fn spawn_camera_zone(
+	mut commands: Commands,
+	level_size: LevelSize,
+	q_spawned_ldtk_entities: Query<&EntityInstance, Added<EntityInstance>>,
+) {
+	for instance in q_spawned_ldtk_entities
+		.iter()
+		.filter(|e| e.identifier == "CameraZone")
+	{
+		if let Err(e) = (|| {
+			let zoom = *instance.get_float_field("zoom")?;
+			let offset = Vec2::new(
+				*instance.get_float_field("offset_x")?,
+				*instance.get_float_field("offset_y")?,
+			);
+			let locked_axis = match instance.get_maybe_string_field("locked_axis")? {
+				Some(axis) if axis == "x" => Some(LockedAxis::X),
+				Some(axis) if axis == "y" => Some(LockedAxis::Y),
+				Some(axis) => return Err(format!("unknown axis `{axis}`").into()),
+				None => None,
+			};
+
+			// LDtk measures from the top left, in pixels
+			let top_left = Vec2::new(
+				instance.grid.x as f32,
+				level_size.get().y as f32 - instance.grid.y as f32,
+			);
+			let size = Vec2::new(instance.width as f32, instance.height as f32) / 16.0;
+			let rect = Rect::from_corners(top_left, top_left + Vec2::new(size.x, -size.y));
+
+			commands.spawn((
+				CameraZone {
+					rect,
+					zoom,
+					offset,
+					locked_axis,
+				},
+				Exit(AppState::Game),
+			));
+			Result::<_, Box<dyn Error>>::Ok(())
+		})() {
+			warn!("failed to spawn camera zone: {e}");
+		}
+	}
+}
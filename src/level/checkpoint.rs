@@ -0,0 +1,81 @@
+///
+/// Checkpoints, only active in practice mode
+///
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_hanabi::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::LevelSize;
+use crate::{
+	game::{grid_to_world, practice::PracticeMode},
+	states::{AppState, Exit},
+	video::GraphicsQuality,
+};
+
+const CHECKPOINT_SIZE: f32 = 2.0;
+
+#[derive(Component)]
+pub struct Checkpoint;
+
+pub fn spawn_checkpoint(
+	mut commands: Commands,
+	mut effects: ResMut<Assets<EffectAsset>>,
+	quality: Res<GraphicsQuality>,
+	practice_mode: Res<PracticeMode>,
+	level_size: LevelSize,
+	q_spawned_ldtk_entities: Query<&ldtk::EntityInstance, Added<ldtk::EntityInstance>>,
+) {
+	// Normal runs ignore checkpoints so that records stay comparable
+	if !practice_mode.0 {
+		return;
+	}
+
+	for checkpoint in q_spawned_ldtk_entities
+		.iter()
+		.filter(|e| e.identifier == "Checkpoint")
+	{
+		let mut gradient = Gradient::new();
+		gradient.add_key(0.0, Vec4::new(0.3, 1.0, 0.4, 1.0));
+		gradient.add_key(1.0, Vec4::new(0.3, 1.0, 0.4, 0.0));
+
+		let spawner = quality.spawner(5.0);
+		let effect = effects.add(
+			EffectAsset {
+				name: "CheckpointEffect".into(),
+				capacity: quality.effect_capacity,
+				spawner,
+				..Default::default()
+			}
+			.init(InitPositionCircleModifier {
+				center: Vec3::ZERO,
+				axis: Vec3::Z,
+				radius: 0.5,
+				dimension: ShapeDimension::Surface,
+			})
+			.init(InitVelocityCircleModifier {
+				center: Vec3::ZERO,
+				axis: Vec3::Z,
+				speed: 0.2f32.into(),
+			})
+			.init(InitLifetimeModifier {
+				lifetime: 3_f32.into(),
+			})
+			.render(SizeOverLifetimeModifier {
+				gradient: Gradient::constant(Vec2::splat(0.3)),
+			})
+			.render(ColorOverLifetimeModifier { gradient }),
+		);
+
+		commands.spawn((
+			Checkpoint,
+			ParticleEffect::new(effect).with_z_layer_2d(Some(0.1)),
+			SpatialBundle::from_transform(Transform::from_translation(
+				grid_to_world(&level_size, checkpoint.grid).extend(0.0),
+			)),
+			Collider::ball(CHECKPOINT_SIZE / 2.0),
+			Sensor,
+			Exit(AppState::Game),
+		));
+	}
+}
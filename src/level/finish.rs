@@ -10,6 +10,7 @@ use super::LevelSize;
 use crate::{
 	game::grid_to_world,
 	states::{AppState, Exit},
+	video::GraphicsQuality,
 };
 
 const FINISH_SIZE: f32 = 2.0;
@@ -36,6 +37,7 @@ impl Default for FinishBundle {
 pub fn spawn_finish(
 	mut commands: Commands,
 	mut effects: ResMut<Assets<EffectAsset>>,
+	quality: Res<GraphicsQuality>,
 	level_size: LevelSize,
 	q_spawned_ldtk_entities: Query<&ldtk::EntityInstance, Added<ldtk::EntityInstance>>,
 ) {
@@ -47,11 +49,11 @@ pub fn spawn_finish(
 		gradient.add_key(0.0, Vec4::new(0.5, 0.5, 1.0, 1.0));
 		gradient.add_key(1.0, Vec4::new(0.5, 0.5, 1.0, 0.0));
 
-		let spawner = Spawner::rate(10.0.into());
+		let spawner = quality.spawner(10.0);
 		let effect = effects.add(
 			EffectAsset {
 				name: "FinishEffect".into(),
-				capacity: 4096,
+				capacity: quality.effect_capacity,
 				spawner,
 				..Default::default()
 			}
@@ -5,15 +5,17 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::ldtk::{self, ldtk_fields::LdtkFields};
 use bevy_hanabi::{
 	AccelModifier, ColorOverLifetimeModifier, EffectAsset, Gradient, InitLifetimeModifier,
-	InitPositionCircleModifier, ParticleEffect, ShapeDimension, SizeOverLifetimeModifier, Spawner,
+	InitPositionCircleModifier, ParticleEffect, ShapeDimension, SizeOverLifetimeModifier,
 };
 use bevy_rapier2d::prelude::*;
 
 use super::LevelSize;
 use crate::{
-	game::grid_to_world,
+	game::{grid_to_world, GameplayEvent},
 	player::Player,
+	savestate::SavestateKey,
 	states::{AppState, Exit},
+	video::GraphicsQuality,
 };
 
 pub struct LaunchpadPlugin;
@@ -29,6 +31,7 @@ impl Plugin for LaunchpadPlugin {
 fn spawn_launchpad(
 	mut commands: Commands,
 	mut effects: ResMut<Assets<EffectAsset>>,
+	quality: Res<GraphicsQuality>,
 	level_size: LevelSize,
 	q_spawned_ldtk_entities: Query<&ldtk::EntityInstance, Added<ldtk::EntityInstance>>,
 ) {
@@ -39,12 +42,15 @@ fn spawn_launchpad(
 		if let Err(e) = (|| {
 			let x = *instance.get_float_field("x")?;
 			let y = *instance.get_float_field("y")?;
+			let pos = grid_to_world(&level_size, instance.grid);
 			commands.spawn((
 				LaunchpadBundle::new(
-					grid_to_world(&level_size, instance.grid),
+					pos,
 					Vec2::new(x, y),
 					&mut effects,
+					&quality,
 				),
+				SavestateKey::from_spawn_pos(pos),
 				Exit(AppState::Game),
 			));
 			Result::<_, Box<dyn Error>>::Ok(())
@@ -69,16 +75,21 @@ pub struct LaunchpadBundle {
 	pub effect: ParticleEffect,
 }
 impl LaunchpadBundle {
-	fn new(pos: Vec2, vel: Vec2, effects: &mut Assets<EffectAsset>) -> Self {
+	fn new(
+		pos: Vec2,
+		vel: Vec2,
+		effects: &mut Assets<EffectAsset>,
+		quality: &GraphicsQuality,
+	) -> Self {
 		let mut gradient = Gradient::new();
 		gradient.add_key(0.0, Vec4::new(8.0, 0.7, 0.2, 1.0));
 		gradient.add_key(1.0, Vec4::new(8.0, 0.7, 0.2, 0.0));
 
-		let spawner = Spawner::rate(10.0.into());
+		let spawner = quality.spawner(10.0);
 		let effect = effects.add(
 			EffectAsset {
 				name: "FinishEffect".into(),
-				capacity: 4096,
+				capacity: quality.effect_capacity,
 				spawner,
 				..Default::default()
 			}
@@ -108,8 +119,7 @@ impl LaunchpadBundle {
 
 /// Launches the player when they get nearby
 fn update_launchpad(
-	asset_server: Res<AssetServer>,
-	audio: Res<Audio>,
+	mut ev_gameplay: EventWriter<GameplayEvent>,
 	mut q_player: Query<(&Transform, &mut Velocity), With<Player>>,
 	q_launchpad: Query<(&Transform, &Launchpad), Without<Player>>,
 ) {
@@ -124,10 +134,7 @@ fn update_launchpad(
 			.distance(launchpad_tr.translation.truncate())
 			<= LAUNCHPAD_SIZE
 		{
-			audio.play_with_settings(
-				asset_server.load("sounds/launchpad.ogg"),
-				PlaybackSettings::ONCE.with_volume(0.5),
-			);
+			ev_gameplay.send(GameplayEvent::Launched);
 			player_vel.linvel = launchpad.vel;
 		}
 	}
@@ -0,0 +1,79 @@
+///
+/// Surface materials: how the colliders of each IntGrid value behave, defined in `materials.toml`
+///
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::game::GameplayEvent;
+
+/// Read at build time rather than through the asset server, since colliders need it as soon as
+/// levels spawn, so editing it needs a rebuild
+const MATERIALS: &str = include_str!("../../assets/materials.toml");
+
+#[derive(Clone, Deserialize)]
+pub struct SurfaceMaterial {
+	pub name: String,
+	/// IntGrid values made of this material
+	pub values: Vec<i32>,
+	pub friction: f32,
+	pub restitution: f32,
+	pub restores_jump: bool,
+	/// Multiplier of the player's acceleration while they stand on it
+	pub acceleration: f32,
+	pub footstep_sound: Option<String>,
+	pub landing_sound: Option<String>,
+	/// Linear RGB
+	pub particle_color: [f32; 3],
+	/// Only solid from above, see `one_way`
+	#[serde(default)]
+	pub one_way: bool,
+}
+
+impl SurfaceMaterial {
+	pub fn particle_color(&self) -> Color {
+		let [r, g, b] = self.particle_color;
+		Color::rgb_linear(r, g, b)
+	}
+}
+
+#[derive(Deserialize, Resource)]
+pub struct MaterialTable {
+	#[serde(rename = "material")]
+	pub materials: Vec<SurfaceMaterial>,
+}
+
+impl MaterialTable {
+	pub fn load() -> Self {
+		toml::from_str(MATERIALS).unwrap_or_else(|e| {
+			error!("failed to load materials: {e}");
+			Self { materials: vec![] }
+		})
+	}
+
+	/// Material of an IntGrid value, by index in the table
+	pub fn find(&self, value: i32) -> Option<usize> {
+		self.materials
+			.iter()
+			.position(|m| m.values.contains(&value))
+	}
+
+	pub fn get(&self, surface: Surface) -> &SurfaceMaterial {
+		&self.materials[surface.0]
+	}
+
+	/// Sound of a gameplay event on a material, for the events that depend on it
+	pub fn sound(&self, surface: Surface, event: GameplayEvent) -> Option<&str> {
+		let material = self.get(surface);
+		match event {
+			GameplayEvent::Landed => material.landing_sound.as_deref(),
+			GameplayEvent::Footstep => material.footstep_sound.as_deref(),
+			_ => None,
+		}
+	}
+}
+
+/// Material of a collider, by index in the table
+#[derive(
+	Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Component, Reflect, FromReflect,
+)]
+pub struct Surface(pub usize);
@@ -1,14 +1,20 @@
 /// Handles the level logic
 /// Sub-plugins handle the interactable elements (launchpads, portals, ...)
+pub mod camera_zone;
+pub mod checkpoint;
 pub mod finish;
 pub mod launchpad;
+pub mod material;
+pub mod moving_platform;
+pub mod one_way;
 mod portal;
-mod rope;
+pub mod rope;
+pub mod slope;
 pub mod start;
 mod text;
 
 use bevy::{
-	ecs::system::SystemParam,
+	ecs::system::{EntityCommands, SystemParam},
 	prelude::*,
 	utils::{HashMap, HashSet},
 };
@@ -17,6 +23,11 @@ use bevy_ecs_ldtk::{
 };
 use bevy_rapier2d::prelude::*;
 
+use self::{
+	material::{MaterialTable, Surface},
+	one_way::OneWayPlatform,
+	slope::Slope,
+};
 use crate::states::AppState;
 
 pub struct LevelPlugin;
@@ -27,6 +38,7 @@ impl Plugin for LevelPlugin {
 			.add_plugin(launchpad::LaunchpadPlugin)
 			.add_plugin(portal::PortalPlugin)
 			.add_plugin(rope::RopePlugin)
+			.add_plugin(moving_platform::MovingPlatformPlugin)
 			.add_plugin(LdtkPlugin)
 			.configure_set(LdtkSystemSet::ProcessApi.before(PhysicsSet::SyncBackend))
 			.insert_resource(LevelSelection::Index(0))
@@ -35,17 +47,24 @@ impl Plugin for LevelPlugin {
 				level_background: LevelBackground::Nonexistent,
 				..default()
 			})
+			.insert_resource(MaterialTable::load())
 			.add_startup_system(setup_level)
-			.register_ldtk_int_cell::<WallBundle>(1)
-			.register_ldtk_int_cell::<IceBundle>(2)
 			.register_ldtk_int_cell::<BackgroundLightBundle>(4)
-			.add_system(spawn_wall_collision::<Wall>)
-			.add_system(spawn_wall_collision::<Ice>)
+			.add_system(spawn_wall_collision)
+			.add_system(slope::spawn_slope_collision)
 			.add_system(background_light_spawn)
 			.add_systems(
-				(start::spawn_start, finish::spawn_finish)
+				(
+					start::spawn_start,
+					finish::spawn_finish,
+					checkpoint::spawn_checkpoint,
+					camera_zone::spawn_camera_zone,
+				)
 					.distributive_run_if(in_state(AppState::Game)),
 			);
+		for value in slope::SLOPE_VALUES {
+			app.register_ldtk_int_cell::<slope::SlopeBundle>(value);
+		}
 	}
 }
 
@@ -66,28 +85,14 @@ pub struct LevelSize<'w, 's> {
 }
 impl<'w, 's> LevelSize<'w, 's> {
 	pub fn get(&self) -> UVec2 {
-		let layer = self
-			.layer
-			.iter()
-			.next()
-			.expect("couldn't get level size: no level loaded");
-		UVec2::new(layer.c_wid as u32, layer.c_hei as u32)
+		self.try_get().expect("couldn't get level size: no level loaded")
 	}
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
-pub struct Wall;
 
-#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
-pub struct WallBundle {
-	wall: Wall,
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
-pub struct Ice;
-#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
-pub struct IceBundle {
-	ice: Ice,
+	/// Size of the level, if one is loaded
+	pub fn try_get(&self) -> Option<UVec2> {
+		let layer = self.layer.iter().next()?;
+		Some(UVec2::new(layer.c_wid as u32, layer.c_hei as u32))
+	}
 }
 
 #[derive(Component)]
@@ -96,27 +101,29 @@ pub struct WallCollider;
 #[derive(Component)]
 pub struct RestoresJump;
 
-/// Helper trait for the behavior of different tiles
-pub trait MapTile: Component {
-	fn restores_jump() -> bool {
-		false
+/// Gives a collider the physical properties of its material
+pub fn insert_surface(cmds: &mut EntityCommands, table: &MaterialTable, surface: Surface) {
+	let material = table.get(surface);
+	cmds.insert((
+		surface,
+		Friction::new(material.friction),
+		Restitution::coefficient(material.restitution),
+	));
+	if material.restores_jump {
+		cmds.insert(RestoresJump);
 	}
-}
-
-impl MapTile for Wall {
-	fn restores_jump() -> bool {
-		true
+	if material.one_way {
+		cmds.insert((OneWayPlatform, ActiveHooks::MODIFY_SOLVER_CONTACTS));
 	}
 }
 
-impl MapTile for Ice {}
-
-/// Spawn colliders for component T
+/// Spawn colliders for the IntGrid values that have a material, merging cells of the same material
 /// Adapted from the `bevy_ecs_ldtk` example
-pub fn spawn_wall_collision<T: MapTile>(
+pub fn spawn_wall_collision(
 	mut commands: Commands,
-	wall_query: Query<(&GridCoords, &Parent), Added<T>>,
-	parent_query: Query<&Parent, Without<T>>,
+	table: Res<MaterialTable>,
+	wall_query: Query<(&GridCoords, &IntGridCell, &Parent), (Added<IntGridCell>, Without<Slope>)>,
+	parent_query: Query<&Parent, Without<IntGridCell>>,
 	level_query: Query<(Entity, &Handle<LdtkLevel>)>,
 	levels: Res<Assets<LdtkLevel>>,
 ) {
@@ -139,27 +146,32 @@ pub fn spawn_wall_collision<T: MapTile>(
 	// Consider where the walls are
 	// storing them as GridCoords in a HashSet for quick, easy lookup
 	//
-	// The key of this map will be the entity of the level the wall belongs to.
-	// This has two consequences in the resulting collision entities:
+	// The key of this map will be the entity of the level the wall belongs to, and its material.
+	// This has three consequences in the resulting collision entities:
 	// 1. it forces the walls to be split along level boundaries
 	// 2. it lets us easily add the collision entities as children of the appropriate level entity
-	let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+	// 3. walls of different materials are never merged
+	let mut level_to_wall_locations: HashMap<(Entity, Surface), HashSet<GridCoords>> =
+		HashMap::new();
 
-	wall_query.for_each(|(&grid_coords, parent)| {
+	wall_query.for_each(|(&grid_coords, cell, parent)| {
+		let Some(material) = table.find(cell.value) else {
+			return;
+		};
 		// An intgrid tile's direct parent will be a layer entity, not the level entity
 		// To get the level entity, you need the tile's grandparent.
 		// This is where parent_query comes in.
 		if let Ok(grandparent) = parent_query.get(parent.get()) {
 			level_to_wall_locations
-				.entry(grandparent.get())
+				.entry((grandparent.get(), Surface(material)))
 				.or_default()
 				.insert(grid_coords);
 		}
 	});
 
-	if !wall_query.is_empty() {
-		level_query.for_each(|(level_entity, level_handle)| {
-			if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
+	for ((level_entity, surface), level_walls) in level_to_wall_locations {
+		if let Ok((level_entity, level_handle)) = level_query.get(level_entity) {
+			{
 				let level = levels
 					.get(level_handle)
 					.expect("Level should be loaded by this point");
@@ -245,7 +257,6 @@ pub fn spawn_wall_collision<T: MapTile>(
 								* grid_size as f32 / 2.,
 						))
 						.insert(RigidBody::Fixed)
-						.insert(Friction::new(1.0))
 						.insert(Transform::from_xyz(
 							(wall_rect.left + wall_rect.right + 1) as f32 * grid_size as f32 / 2.,
 							(wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size as f32 / 2.,
@@ -253,13 +264,11 @@ pub fn spawn_wall_collision<T: MapTile>(
 						))
 						.insert(GlobalTransform::default())
 						.insert(WallCollider);
-						if T::restores_jump() {
-							cmds.insert(RestoresJump);
-						}
+						insert_surface(&mut cmds, &table, surface);
 					}
 				});
 			}
-		});
+		}
 	}
 }
 
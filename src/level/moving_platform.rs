@@ -0,0 +1,259 @@
+///
+/// Moving platforms: kinematic bodies that follow a path drawn in LDtk, carrying the player
+/// Their position only depends on how long the run has lasted in ticks, so replays, ghosts
+/// & savestates see them in the same place, & is interpolated between ticks since the
+/// physics steps every frame
+///
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::ldtk_fields::LdtkFields, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use super::{
+	insert_surface,
+	material::{MaterialTable, Surface},
+	LevelSize, WallCollider,
+};
+use crate::{
+	game::practice::ActiveCheckpoint,
+	player::{Player, PlayerGroundSensor},
+	replay::ReplayRecording,
+	states::{AppState, Exit},
+};
+
+/// IntGrid value whose material platforms are made of
+const PLATFORM_VALUE: i32 = 1;
+
+pub struct MovingPlatformPlugin;
+
+impl Plugin for MovingPlatformPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Riding>().add_systems(
+			(
+				spawn_moving_platform,
+				platform_move.after(spawn_moving_platform),
+				platform_carry.after(platform_move),
+			)
+				.distributive_run_if(in_state(AppState::Game)),
+		);
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+	Linear,
+	/// Slows down when getting to each point
+	Smooth,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+	/// Goes back along the path once at the end
+	PingPong,
+	/// Goes from the last point straight to the first one
+	Loop,
+}
+
+#[derive(Component)]
+pub struct MovingPlatform {
+	/// Positions of the center of the platform, in world coordinates
+	points: Vec<Vec2>,
+	/// In tiles per second
+	speed: f32,
+	easing: Easing,
+	mode: PathMode,
+	/// Movement during the current frame, which the player follows when riding it
+	displacement: Vec2,
+	/// Velocity during the current tick, which the player keeps when getting off
+	velocity: Vec2,
+}
+
+impl MovingPlatform {
+	/// Position of the center of the platform after some time
+	pub fn position(&self, elapsed: f32) -> Vec2 {
+		let mut segments: Vec<(Vec2, Vec2)> =
+			self.points.windows(2).map(|w| (w[0], w[1])).collect();
+		match self.mode {
+			PathMode::PingPong => {
+				let back: Vec<_> = segments.iter().rev().map(|(a, b)| (*b, *a)).collect();
+				segments.extend(back);
+			}
+			PathMode::Loop => segments.push((*self.points.last().unwrap(), self.points[0])),
+		}
+		let total: f32 = segments.iter().map(|(a, b)| a.distance(*b)).sum();
+		if total <= 0.0 {
+			return self.points[0];
+		}
+
+		let mut distance = (elapsed * self.speed).rem_euclid(total);
+		for (a, b) in segments {
+			let length = a.distance(b);
+			if distance < length {
+				let t = distance / length;
+				let t = match self.easing {
+					Easing::Linear => t,
+					Easing::Smooth => t * t * (3.0 - 2.0 * t),
+				};
+				return a.lerp(b, t);
+			}
+			distance -= length;
+		}
+		self.points[0]
+	}
+}
+
+/// The platform the player stood on during the last frame
+#[derive(Default, Resource)]
+pub struct Riding {
+	platform: Option<Entity>,
+	tick: usize,
+}
+
+fn spawn_moving_platform(
+	mut commands: Commands,
+	table: Res<MaterialTable>,
+	level_size: LevelSize,
+	q_spawned_ldtk_entities: Query<&EntityInstance, Added<EntityInstance>>,
+) {
+	for instance in q_spawned_ldtk_entities
+		.iter()
+		.filter(|e| e.identifier == "MovingPlatform")
+	{
+		if let Err(e) = (|| {
+			let speed = *instance.get_float_field("speed")?;
+			let easing = match instance.get_maybe_string_field("easing")? {
+				Some(easing) if easing == "smooth" => Easing::Smooth,
+				Some(easing) if easing == "linear" => Easing::Linear,
+				Some(easing) => return Err(format!("unknown easing `{easing}`").into()),
+				None => Easing::Linear,
+			};
+			let mode = match instance.get_maybe_string_field("mode")? {
+				Some(mode) if mode == "ping_pong" => PathMode::PingPong,
+				Some(mode) if mode == "loop" => PathMode::Loop,
+				Some(mode) => return Err(format!("unknown mode `{mode}`").into()),
+				None => PathMode::PingPong,
+			};
+
+			// LDtk measures from the top left, in pixels
+			let size = Vec2::new(instance.width as f32, instance.height as f32) / 16.0;
+			let center = |cell: IVec2| {
+				Vec2::new(cell.x as f32, level_size.get().y as f32 - cell.y as f32)
+					+ Vec2::new(size.x, -size.y) / 2.0
+			};
+			let points: Vec<Vec2> = std::iter::once(instance.grid)
+				.chain(instance.iter_points_field("path")?.copied())
+				.map(center)
+				.collect();
+
+			let mut cmds = commands.spawn((
+				MovingPlatform {
+					points,
+					speed,
+					easing,
+					mode,
+					displacement: Vec2::ZERO,
+					velocity: Vec2::ZERO,
+				},
+				SpriteBundle {
+					sprite: Sprite {
+						color: Color::rgb(0.55, 0.55, 0.55),
+						custom_size: Some(size),
+						..default()
+					},
+					transform: Transform::from_translation(center(instance.grid).extend(0.5)),
+					..default()
+				},
+				RigidBody::KinematicPositionBased,
+				Collider::cuboid(size.x / 2.0, size.y / 2.0),
+				WallCollider,
+				Exit(AppState::Game),
+			));
+			if let Some(material) = table.find(PLATFORM_VALUE) {
+				insert_surface(&mut cmds, &table, Surface(material));
+			}
+			// the player is moved along with the platform, friction would drag them a second time
+			cmds.insert(Friction {
+				coefficient: 0.0,
+				combine_rule: CoefficientCombineRule::Min,
+			});
+			Result::<_, Box<dyn Error>>::Ok(())
+		})() {
+			warn!("failed to spawn moving platform: {e}");
+		}
+	}
+}
+
+/// Moves the platforms to where they are at this point of the run,
+/// between the last tick & the next one
+fn platform_move(
+	fixed_time: Res<FixedTime>,
+	recording: Res<ReplayRecording>,
+	active_checkpoint: Res<ActiveCheckpoint>,
+	mut last_ticks: Local<usize>,
+	mut q_platform: Query<(Entity, &mut MovingPlatform, &mut Transform)>,
+	q_new_platform: Query<(), Added<MovingPlatform>>,
+) {
+	let period = fixed_time.period.as_secs_f32();
+	// the recording restarts with the player, so the ticks before the checkpoint are added
+	let ticks = active_checkpoint.ticks() + recording.0.ticks();
+	let alpha = (fixed_time.accumulated().as_secs_f32() / period).min(1.0);
+	// the run went back in time, the platforms jump rather than move
+	let restored = ticks < *last_ticks;
+	*last_ticks = ticks;
+
+	for (entity, mut platform, mut transform) in &mut q_platform {
+		let from = platform.position(ticks as f32 * period);
+		let to = platform.position((ticks + 1) as f32 * period);
+		let pos = from.lerp(to, alpha);
+		platform.displacement = if restored || q_new_platform.contains(entity) {
+			Vec2::ZERO
+		} else {
+			pos - transform.translation.truncate()
+		};
+		platform.velocity = (to - from) / period;
+		transform.translation = pos.extend(transform.translation.z);
+	}
+}
+
+/// Moves the player along with the platform they stand on,
+/// and leaves them its velocity when they get off
+fn platform_carry(
+	mut riding: ResMut<Riding>,
+	recording: Res<ReplayRecording>,
+	active_checkpoint: Res<ActiveCheckpoint>,
+	mut q_player: Query<(&mut Transform, &mut Velocity), With<Player>>,
+	q_new_player: Query<(), Added<Player>>,
+	q_sensor: Query<&CollidingEntities, With<PlayerGroundSensor>>,
+	q_platform: Query<&MovingPlatform>,
+) {
+	let tick = active_checkpoint.ticks() + recording.0.ticks();
+	// a new player, or a restored run, didn't get off anything
+	if !q_new_player.is_empty() || tick < riding.tick {
+		riding.platform = None;
+	}
+	riding.tick = tick;
+
+	let Ok((mut transform, mut velocity)) = q_player.get_single_mut() else {
+		return;
+	};
+	let Ok(ground_sensor) = q_sensor.get_single() else {
+		return;
+	};
+
+	let platform = ground_sensor.iter().find(|e| q_platform.contains(*e));
+	match (platform, riding.platform) {
+		(Some(platform), _) => {
+			let displacement = q_platform.get(platform).unwrap().displacement;
+			transform.translation += displacement.extend(0.0);
+		}
+		// jumping off keeps the momentum of the platform
+		(None, Some(previous)) => {
+			if let Ok(previous) = q_platform.get(previous) {
+				velocity.linvel += previous.velocity;
+			}
+		}
+		(None, None) => {}
+	}
+	riding.platform = platform;
+}
@@ -0,0 +1,56 @@
+///
+/// One-way platforms: colliders that are only solid from above
+/// Rapier asks us about each of their contacts, and we drop the ones coming from below,
+/// or all of them while the player is dropping through
+///
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::{prelude::*, rapier::math::Vector};
+
+use crate::player::Player;
+
+/// How far from vertical a contact can be while still being stood on, in radians
+const MAX_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Value of `user_data` that `update_as_oneway_platform` uses for forbidden contacts,
+/// so that the platform stays passable until the player is out of it
+const CONTACT_FORBIDDEN: u32 = 2;
+
+#[derive(Component)]
+pub struct OneWayPlatform;
+
+#[derive(SystemParam)]
+pub struct OneWayHooks<'w, 's> {
+	q_platform: Query<'w, 's, (), With<OneWayPlatform>>,
+	q_player: Query<'w, 's, &'static Player>,
+}
+
+impl BevyPhysicsHooks for OneWayHooks<'_, '_> {
+	fn modify_solver_contacts(&self, context: ContactModificationContextView) {
+		let (platform, other) = (context.collider1(), context.collider2());
+		// the normal points out of the first collider, so it's reversed when that's the other one
+		let (other, allowed_normal) = if self.q_platform.contains(platform) {
+			(other, Vector::y())
+		} else if self.q_platform.contains(other) {
+			(platform, -Vector::y())
+		} else {
+			return;
+		};
+
+		if self.q_player.get(other).is_ok_and(|p| p.dropping) {
+			context.raw.solver_contacts.clear();
+			*context.raw.user_data = CONTACT_FORBIDDEN;
+			return;
+		}
+		context
+			.raw
+			.update_as_oneway_platform(&allowed_normal, MAX_ANGLE);
+	}
+}
+
+/// Whether the player stands on a one-way platform, rather than going through it
+/// Its contacts are only kept when it holds the player up, the sensors can't tell the difference
+pub fn stands_on(context: &RapierContext, player: Entity, platform: Entity) -> bool {
+	context
+		.contact_pair(player, platform)
+		.is_some_and(|pair| pair.manifolds().any(|m| m.num_solver_contacts() > 0))
+}
@@ -7,15 +7,17 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::ldtk::{self, ldtk_fields::LdtkFields};
 use bevy_hanabi::{
 	AccelModifier, ColorOverLifetimeModifier, EffectAsset, Gradient, InitLifetimeModifier,
-	InitPositionCircleModifier, ParticleEffect, ShapeDimension, SizeOverLifetimeModifier, Spawner,
+	InitPositionCircleModifier, ParticleEffect, ShapeDimension, SizeOverLifetimeModifier,
 };
 use bevy_rapier2d::prelude::*;
 
 use super::LevelSize;
 use crate::{
-	game::grid_to_world,
+	game::{grid_to_world, GameplayEvent},
 	player::{player_controls, Player},
+	savestate::SavestateKey,
 	states::{AppState, Exit},
+	video::GraphicsQuality,
 };
 
 pub struct PortalPlugin;
@@ -82,6 +84,7 @@ pub struct SpawnPortal {
 fn portal_spawn(
 	mut commands: Commands,
 	mut effects: ResMut<Assets<EffectAsset>>,
+	quality: Res<GraphicsQuality>,
 	mut ev_spawn_portal: EventReader<SpawnPortal>,
 ) {
 	for spawn_portal in ev_spawn_portal.iter() {
@@ -89,11 +92,11 @@ fn portal_spawn(
 		let col = spawn_portal.color.as_rgba_f32();
 		gradient.add_key(0.0, Vec4::new(col[0], col[1], col[2], 1.0));
 		gradient.add_key(1.0, Vec4::new(col[0], col[1], col[2], 0.0));
-		let spawner = Spawner::rate(30.0.into());
+		let spawner = quality.spawner(30.0);
 		let effect = effects.add(
 			EffectAsset {
 				name: "FinishEffect".into(),
-				capacity: 4096,
+				capacity: quality.effect_capacity,
 				spawner,
 				..Default::default()
 			}
@@ -126,17 +129,18 @@ fn portal_spawn(
 			)),
 			Collider::segment(Vec2::X * -1.5, Vec2::X * 1.5),
 			Sensor,
+			SavestateKey::from_spawn_pos(spawn_portal.pos),
 			Exit(AppState::Game),
 		));
 
 		// let mut gradient = Gradient::new();
 		// gradient.add_key(0.0, Vec4::new(0.9, 0.7, 0.2, 1.0));
 		// gradient.add_key(1.0, Vec4::new(0.9, 0.7, 0.2, 0.0));
-		let spawner = Spawner::rate(30.0.into());
+		let spawner = quality.spawner(30.0);
 		let effect = effects.add(
 			EffectAsset {
 				name: "FinishEffect".into(),
-				capacity: 4096,
+				capacity: quality.effect_capacity,
 				spawner,
 				..Default::default()
 			}
@@ -171,8 +175,7 @@ fn portal_spawn(
 }
 
 fn update_portal(
-	asset_server: Res<AssetServer>,
-	audio: Res<Audio>,
+	mut ev_gameplay: EventWriter<GameplayEvent>,
 	mut ev_collision: EventReader<CollisionEvent>,
 	mut q_player: Query<(Entity, &mut Player, &mut Transform, &mut Velocity)>,
 	q_portal: Query<(Entity, &Transform, &Portal), Without<Player>>,
@@ -191,10 +194,7 @@ fn update_portal(
 					if let Some((_, portal_tr, portal)) =
 						[e0, e1].iter().find_map(|e| q_portal.get(**e).ok())
 					{
-						audio.play_with_settings(
-							asset_server.load("sounds/portal.ogg"),
-							PlaybackSettings::ONCE.with_volume(0.5),
-						);
+						ev_gameplay.send(GameplayEvent::Teleported);
 						let offset =
 							player_tr.translation.truncate() - portal_tr.translation.truncate();
 
@@ -11,6 +11,7 @@ use crate::{
 	game::grid_to_world,
 	input::Action,
 	player::{player_controls, Player},
+	savestate::SavestateKey,
 	states::{AppState, Exit},
 };
 
@@ -86,10 +87,13 @@ fn rope_spawn(mut commands: Commands, mut ev_spawn_rope: EventReader<SpawnRope>)
 					.local_anchor1(Vec2::Y * -SEGMENT_SIZE / 2.0)
 					.local_anchor2(Vec2::Y * SEGMENT_SIZE / 2.0)
 			};
+			let pos = spawn_rope.pos - (idx as f32 * Vec2::Y * SEGMENT_SIZE);
 			anchor = commands
 				.spawn((
 					RopeSegment,
+					SavestateKey::from_spawn_pos(pos),
 					RigidBody::Dynamic,
+					Velocity::zero(),
 					ImpulseJoint::new(anchor, joint),
 					Sensor,
 					ColliderMassProperties::Mass(1.0 * SEGMENT_SIZE),
@@ -102,9 +106,7 @@ fn rope_spawn(mut commands: Commands, mut ev_spawn_rope: EventReader<SpawnRope>)
 						-Vec2::Y * SEGMENT_SIZE / 2.0,
 						1.0,
 					),
-					SpatialBundle::from_transform(Transform::from_translation(
-						(spawn_rope.pos - (idx as f32 * Vec2::Y * SEGMENT_SIZE)).extend(0.0),
-					)),
+					SpatialBundle::from_transform(Transform::from_translation(pos.extend(0.0))),
 					Sprite {
 						color: Color::rgb(0.25, 0.25, 0.75) * 3.0,
 						custom_size: Some(Vec2::new(0.2, SEGMENT_SIZE)),
@@ -157,13 +159,17 @@ fn update_rope(
 		if maybe_joint.is_some() {
 			commands.entity(player_entity).remove::<ImpulseJoint>();
 		} else if let Some((e, _)) = colliding_entities.iter().find_map(|e| q_rope.get(e).ok()) {
-			let joint = RevoluteJointBuilder::new()
-				.local_anchor1(Vec2::ZERO)
-				.local_anchor2(Vec2::ZERO);
 			commands
 				.entity(player_entity)
-				.insert(ImpulseJoint::new(e, joint));
+				.insert(ImpulseJoint::new(e, player_rope_joint()));
 			player.remaining_jumps = 1;
 		}
 	}
 }
+
+/// Joint between the player and the rope segment they hold
+pub fn player_rope_joint() -> RevoluteJointBuilder {
+	RevoluteJointBuilder::new()
+		.local_anchor1(Vec2::ZERO)
+		.local_anchor2(Vec2::ZERO)
+}
@@ -0,0 +1,425 @@
+///
+/// Slopes: IntGrid cells that are cut diagonally, with convex polygon colliders
+/// Steep slopes rise a tile per tile (45°), shallow ones half a tile per tile, over a low & a high cell
+/// Neighbouring slopes are merged as long as their union stays convex, so that the player
+/// doesn't catch on the seams. Ramps only touch diagonally, so the solid cells under them
+/// fill the gaps between their cells
+///
+use bevy::{
+	prelude::*,
+	utils::{HashMap, HashSet},
+};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::{
+	insert_surface,
+	material::{MaterialTable, Surface},
+	WallCollider,
+};
+
+/// IntGrid values of the slopes, see `Slope::from`
+pub const SLOPE_VALUES: std::ops::RangeInclusive<i32> = 10..=21;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SlopeShape {
+	/// Cut from corner to corner
+	#[default]
+	Steep,
+	/// First half of a shallow slope, up to half the height
+	ShallowLow,
+	/// Second half of a shallow slope, from half the height
+	ShallowHigh,
+}
+
+/// A slope cell, described as rising to the right with the solid part at the bottom,
+/// then mirrored to get the other orientations
+#[derive(Clone, Copy, Debug, Default, Component)]
+pub struct Slope {
+	shape: SlopeShape,
+	/// Rising to the left
+	flip_x: bool,
+	/// Solid part at the top (a ceiling)
+	flip_y: bool,
+}
+
+/// Values go by shape (steep, shallow low, shallow high),
+/// then by solid corner (bottom right, bottom left, top right, top left)
+impl From<IntGridCell> for Slope {
+	fn from(cell: IntGridCell) -> Self {
+		let idx = cell.value - SLOPE_VALUES.start();
+		let shape = match idx / 4 {
+			0 => SlopeShape::Steep,
+			1 => SlopeShape::ShallowLow,
+			_ => SlopeShape::ShallowHigh,
+		};
+		Self {
+			shape,
+			flip_x: idx % 2 == 1,
+			flip_y: idx % 4 >= 2,
+		}
+	}
+}
+
+impl Slope {
+	/// Corners of the solid part, in tiles from the bottom left of the cell
+	fn polygon(&self) -> Vec<Vec2> {
+		let points: &[Vec2] = match self.shape {
+			SlopeShape::Steep => &[
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 1.0),
+			],
+			SlopeShape::ShallowLow => &[
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 0.5),
+			],
+			SlopeShape::ShallowHigh => &[
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 1.0),
+				Vec2::new(0.0, 0.5),
+			],
+		};
+		points
+			.iter()
+			.map(|p| {
+				Vec2::new(
+					if self.flip_x { 1.0 - p.x } else { p.x },
+					if self.flip_y { 1.0 - p.y } else { p.y },
+				)
+			})
+			.collect()
+	}
+}
+
+/// Collider of merged slope cells, which the player keeps their momentum along
+#[derive(Component)]
+pub struct SlopeCollider;
+
+#[derive(Clone, Default, Bundle, LdtkIntCell)]
+pub struct SlopeBundle {
+	#[from_int_grid_cell]
+	slope: Slope,
+	/// Kept for the material of the slope
+	#[from_int_grid_cell]
+	cell: IntGridCell,
+}
+
+/// Spawns the colliders of the slopes, grouped by level & material like walls are
+pub fn spawn_slope_collision(
+	mut commands: Commands,
+	table: Res<MaterialTable>,
+	slope_query: Query<(&GridCoords, &Slope, &IntGridCell, &Parent), Added<Slope>>,
+	solid_query: Query<(&GridCoords, &IntGridCell, &Parent), Without<Slope>>,
+	parent_query: Query<&Parent, Without<IntGridCell>>,
+	level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+	levels: Res<Assets<LdtkLevel>>,
+) {
+	let mut level_to_slopes: HashMap<(Entity, Surface), HashMap<IVec2, Slope>> = HashMap::new();
+	slope_query.for_each(|(&grid_coords, slope, cell, parent)| {
+		let Some(material) = table.find(cell.value) else {
+			return;
+		};
+		// the direct parent of a cell is its layer, the level is above it
+		if let Ok(grandparent) = parent_query.get(parent.get()) {
+			level_to_slopes
+				.entry((grandparent.get(), Surface(material)))
+				.or_default()
+				.insert(IVec2::from(grid_coords), *slope);
+		}
+	});
+	if level_to_slopes.is_empty() {
+		return;
+	}
+
+	// cells that are solid whatever their material, one-way platforms can be gone through
+	let mut level_to_solids: HashMap<Entity, HashSet<IVec2>> = HashMap::new();
+	solid_query.for_each(|(&grid_coords, cell, parent)| {
+		let solid = table
+			.find(cell.value)
+			.is_some_and(|m| !table.materials[m].one_way);
+		if !solid {
+			return;
+		}
+		if let Ok(grandparent) = parent_query.get(parent.get()) {
+			level_to_solids
+				.entry(grandparent.get())
+				.or_default()
+				.insert(IVec2::from(grid_coords));
+		}
+	});
+
+	for ((level_entity, surface), slopes) in level_to_slopes {
+		let Ok((level_entity, level_handle)) = level_query.get(level_entity) else {
+			continue;
+		};
+		let level = levels
+			.get(level_handle)
+			.expect("Level should be loaded by this point");
+		let grid_size = level
+			.level
+			.layer_instances
+			.as_ref()
+			.expect("Level asset should have layers")[0]
+			.grid_size as f32;
+
+		let solids = level_to_solids.remove(&level_entity).unwrap_or_default();
+		commands.entity(level_entity).with_children(|level| {
+			for polygon in merge_slopes(&slopes, &solids) {
+				let points: Vec<Vec2> = polygon.iter().map(|p| *p * grid_size).collect();
+				let Some(collider) = Collider::convex_hull(&points) else {
+					continue;
+				};
+				let mut cmds = level.spawn((
+					collider,
+					RigidBody::Fixed,
+					TransformBundle::default(),
+					WallCollider,
+					SlopeCollider,
+				));
+				insert_surface(&mut cmds, &table, surface);
+			}
+		});
+	}
+}
+
+/// Groups slope cells into convex polygons, in tiles
+/// Cells are added to a group one neighbour at a time, diagonals included, as long as the
+/// group's hull is covered by its cells & the solid cells around them
+fn merge_slopes(slopes: &HashMap<IVec2, Slope>, solids: &HashSet<IVec2>) -> Vec<Vec<Vec2>> {
+	const NEIGHBOURS: [IVec2; 8] = [
+		IVec2::X,
+		IVec2::Y,
+		IVec2::NEG_X,
+		IVec2::NEG_Y,
+		IVec2::ONE,
+		IVec2::NEG_ONE,
+		IVec2::new(1, -1),
+		IVec2::new(-1, 1),
+	];
+
+	let cell_polygon = |coords: IVec2| -> Vec<Vec2> {
+		slopes[&coords]
+			.polygon()
+			.into_iter()
+			.map(|p| p + coords.as_vec2())
+			.collect()
+	};
+
+	// sorted so that levels always get the same colliders
+	let mut cells: Vec<IVec2> = slopes.keys().copied().collect();
+	cells.sort_by_key(|c| (c.y, c.x));
+
+	let mut merged: HashSet<IVec2> = HashSet::new();
+	let mut polygons = Vec::new();
+	for start in cells {
+		if !merged.insert(start) {
+			continue;
+		}
+		let mut group = vec![start];
+		let mut polygon = cell_polygon(start);
+		// only counts the slope cells, solid cells have colliders of their own
+		let mut area = polygon_area(&polygon);
+
+		let mut grew = true;
+		while grew {
+			grew = false;
+			for neighbour in group
+				.iter()
+				.flat_map(|c| NEIGHBOURS.map(|n| *c + n))
+				.collect::<Vec<_>>()
+			{
+				if merged.contains(&neighbour) || !slopes.contains_key(&neighbour) {
+					continue;
+				}
+				let other = cell_polygon(neighbour);
+				let other_area = polygon_area(&other);
+				let hull = convex_hull(polygon.iter().chain(other.iter()).copied().collect());
+				let filled = solid_area(&hull, solids);
+				// the hull covers more than all the parts when they leave a gap
+				if (polygon_area(&hull) - area - other_area - filled).abs() < 1e-4 {
+					merged.insert(neighbour);
+					group.push(neighbour);
+					polygon = hull;
+					area += other_area;
+					grew = true;
+				}
+			}
+		}
+		polygons.push(polygon);
+	}
+	polygons
+}
+
+/// Convex hull, counter-clockwise (Andrew's monotone chain)
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+	points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+	points.dedup();
+	if points.len() < 3 {
+		return points;
+	}
+
+	let cross = |o: Vec2, a: Vec2, b: Vec2| (a - o).perp_dot(b - o);
+	let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() * 2);
+	for pass in [points.clone(), points.into_iter().rev().collect()] {
+		let start = hull.len();
+		for p in pass {
+			while hull.len() >= start + 2
+				&& cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+			{
+				hull.pop();
+			}
+			hull.push(p);
+		}
+		// the last point is the first one of the other half
+		hull.pop();
+	}
+	hull
+}
+
+/// Area of the solid cells inside a convex polygon
+fn solid_area(polygon: &[Vec2], solids: &HashSet<IVec2>) -> f32 {
+	let min = polygon
+		.iter()
+		.fold(Vec2::splat(f32::INFINITY), |m, p| m.min(*p));
+	let max = polygon
+		.iter()
+		.fold(Vec2::splat(f32::NEG_INFINITY), |m, p| m.max(*p));
+	let mut area = 0.0;
+	for y in min.y.floor() as i32..max.y.ceil() as i32 {
+		for x in min.x.floor() as i32..max.x.ceil() as i32 {
+			if solids.contains(&IVec2::new(x, y)) {
+				area += area_inside(polygon, IVec2::new(x, y));
+			}
+		}
+	}
+	area
+}
+
+/// Area of the part of a cell inside a convex counter-clockwise polygon
+/// The cell is clipped by each edge of the polygon in turn (Sutherland-Hodgman)
+fn area_inside(polygon: &[Vec2], cell: IVec2) -> f32 {
+	let mut clipped: Vec<Vec2> = [IVec2::ZERO, IVec2::X, IVec2::ONE, IVec2::Y]
+		.map(|c| (cell + c).as_vec2())
+		.to_vec();
+	for (i, a) in polygon.iter().enumerate() {
+		let b = polygon[(i + 1) % polygon.len()];
+		let side = |p: Vec2| (b - *a).perp_dot(p - *a);
+		let points = std::mem::take(&mut clipped);
+		for (j, p) in points.iter().enumerate() {
+			let q = points[(j + 1) % points.len()];
+			let (side_p, side_q) = (side(*p), side(q));
+			if side_p >= 0.0 {
+				clipped.push(*p);
+			}
+			if (side_p >= 0.0) != (side_q >= 0.0) {
+				clipped.push(*p + (q - *p) * (side_p / (side_p - side_q)));
+			}
+		}
+	}
+	polygon_area(&clipped)
+}
+
+/// Area of a simple polygon (shoelace formula)
+fn polygon_area(polygon: &[Vec2]) -> f32 {
+	let n = polygon.len();
+	(0..n)
+		.map(|i| polygon[i].perp_dot(polygon[(i + 1) % n]))
+		.sum::<f32>()
+		.abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Steep slope rising to the right
+	const RAMP: Slope = Slope {
+		shape: SlopeShape::Steep,
+		flip_x: false,
+		flip_y: false,
+	};
+
+	#[test]
+	fn hull_drops_inner_points() {
+		let hull = convex_hull(vec![
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 1.0),
+			Vec2::new(2.0, 0.0),
+			Vec2::new(2.0, 2.0),
+			Vec2::new(0.0, 2.0),
+			Vec2::new(0.0, 0.0),
+		]);
+		assert_eq!(
+			hull,
+			vec![
+				Vec2::new(0.0, 0.0),
+				Vec2::new(2.0, 0.0),
+				Vec2::new(2.0, 2.0),
+				Vec2::new(0.0, 2.0),
+			]
+		);
+	}
+
+	#[test]
+	fn hull_keeps_only_corners_of_edges() {
+		let hull = convex_hull(vec![
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 0.0),
+			Vec2::new(2.0, 0.0),
+			Vec2::new(1.0, 1.0),
+		]);
+		assert_eq!(hull.len(), 3);
+		assert_eq!(polygon_area(&hull), 1.0);
+	}
+
+	#[test]
+	fn merges_ramp_over_solid_cells() {
+		// a ramp going up 3 tiles, on top of a staircase of solid cells
+		let slopes = (0..3).map(|i| (IVec2::splat(i), RAMP)).collect();
+		let solids = [IVec2::new(1, 0), IVec2::new(2, 0), IVec2::new(2, 1)]
+			.into_iter()
+			.collect();
+		let polygons = merge_slopes(&slopes, &solids);
+		assert_eq!(polygons.len(), 1);
+		assert!(polygons[0].contains(&Vec2::ZERO));
+		assert!(polygons[0].contains(&Vec2::splat(3.0)));
+	}
+
+	#[test]
+	fn keeps_floating_ramp_apart() {
+		// nothing fills the gaps under the ramp
+		let slopes = (0..3).map(|i| (IVec2::splat(i), RAMP)).collect();
+		let polygons = merge_slopes(&slopes, &HashSet::new());
+		assert_eq!(polygons.len(), 3);
+	}
+
+	#[test]
+	fn merges_side_by_side_slopes_while_convex() {
+		// the low & high halves of a shallow slope make one triangle
+		let low = Slope {
+			shape: SlopeShape::ShallowLow,
+			..RAMP
+		};
+		let high = Slope {
+			shape: SlopeShape::ShallowHigh,
+			..RAMP
+		};
+		let slopes = [(IVec2::ZERO, low), (IVec2::X, high)].into_iter().collect();
+		let polygons = merge_slopes(&slopes, &HashSet::new());
+		assert_eq!(polygons.len(), 1);
+		assert_eq!(polygons[0].len(), 3);
+
+		// a valley isn't convex
+		let falling = Slope {
+			flip_x: true,
+			..RAMP
+		};
+		let valley = [(IVec2::ZERO, falling), (IVec2::X, RAMP)]
+			.into_iter()
+			.collect();
+		assert_eq!(merge_slopes(&valley, &HashSet::new()).len(), 2);
+	}
+}
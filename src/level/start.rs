@@ -1,12 +1,16 @@
 ///
 /// Start of the level
 /// Automatically spawns the player when the start entity appears
+/// (or on the active checkpoint in practice mode)
 ///
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
 use super::LevelSize;
-use crate::{game::grid_to_world, player::SpawnPlayer};
+use crate::{
+	game::{grid_to_world, practice::ActiveCheckpoint},
+	player::SpawnPlayer,
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Start;
@@ -14,6 +18,7 @@ pub struct Start;
 pub fn spawn_start(
 	mut commands: Commands,
 	level_size: LevelSize,
+	active_checkpoint: Res<ActiveCheckpoint>,
 	q_spawned_ldtk_entities: Query<(Entity, &ldtk::EntityInstance), Added<ldtk::EntityInstance>>,
 	mut ev_spawn_player: EventWriter<SpawnPlayer>,
 ) {
@@ -23,8 +28,15 @@ pub fn spawn_start(
 	{
 		commands.entity(entity).insert(Start);
 
-		ev_spawn_player.send(SpawnPlayer {
-			pos: grid_to_world(&level_size, spawn.grid),
+		ev_spawn_player.send(match &active_checkpoint.0 {
+			Some(checkpoint) => SpawnPlayer {
+				pos: checkpoint.pos,
+				vel: checkpoint.vel,
+			},
+			None => SpawnPlayer {
+				pos: grid_to_world(&level_size, spawn.grid),
+				vel: Vec2::ZERO,
+			},
 		});
 	}
 }
@@ -1,13 +1,19 @@
 /// Floating text elements
+/// `<action>` tokens are replaced by what the action is bound to, e.g. `Press <jump> to jump`
 use std::error::Error;
 
+use anyhow::{anyhow, Result};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::ldtk::{self, ldtk_fields::LdtkFields};
 
 use super::LevelSize;
 use crate::{
 	game::grid_to_world,
-	input::CurrentInputMode,
+	input::{
+		gamepad::ActiveGamepad,
+		glyphs::{binding_name, ControllerFamily},
+		Action, Binding, CurrentInputMode, InputMapping,
+	},
 	states::{AppState, Exit},
 };
 
@@ -19,27 +25,99 @@ impl Plugin for TextPlugin {
 	}
 }
 
+/// Part of a floating text
+pub enum TextPart {
+	Literal(String),
+	/// Replaced by the binding of the action
+	Action(Action),
+}
+
 #[derive(Component)]
-pub struct FloatingText(String);
+pub struct FloatingText(Vec<TextPart>);
+
+fn parse_text(source: &str) -> Result<Vec<TextPart>> {
+	let mut parts = vec![];
+	let mut rest = source;
+	while let Some(start) = rest.find('<') {
+		let Some(len) = rest[start..].find('>') else {
+			break;
+		};
+		let token = &rest[start + 1..start + len];
+		let action = Action::from_token(token)
+			.ok_or_else(|| anyhow!("unknown token <{token}> in {source:?}"))?;
+		parts.push(TextPart::Literal(rest[..start].to_owned()));
+		parts.push(TextPart::Action(action));
+		rest = &rest[start + len + 1..];
+	}
+	parts.push(TextPart::Literal(rest.to_owned()));
+	Ok(parts)
+}
+
+/// What is shown to the player for each action
+struct Glyphs<'a> {
+	mapping: &'a InputMapping,
+	input_mode: CurrentInputMode,
+	device: Option<&'a str>,
+}
+
+impl<'a> Glyphs<'a> {
+	fn render(&self, parts: &[TextPart]) -> String {
+		parts
+			.iter()
+			.map(|part| match part {
+				TextPart::Literal(s) => s.clone(),
+				TextPart::Action(action) => self.glyph(*action),
+			})
+			.collect()
+	}
+
+	fn glyph(&self, action: Action) -> String {
+		let family = ControllerFamily::from_name(self.device.unwrap_or_default());
+		let binding = match self.input_mode {
+			CurrentInputMode::Keyboard => self.mapping.actions[&action]
+				.keys
+				.first()
+				.map(|k| Binding::Key(*k)),
+			CurrentInputMode::Gamepad => self
+				.mapping
+				.buttons(action, self.device)
+				.first()
+				.map(|b| Binding::Gamepad(*b)),
+		};
+		match binding {
+			Some(binding) => binding_name(binding, family),
+			None => "(unbound)".to_owned(),
+		}
+	}
+}
 
 fn spawn_text(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
 	level_size: LevelSize,
+	mapping: Res<InputMapping>,
+	input_mode: Res<CurrentInputMode>,
+	gamepads: Res<Gamepads>,
+	active_gamepad: Res<ActiveGamepad>,
 	q_spawned_ldtk_entities: Query<&ldtk::EntityInstance, Added<ldtk::EntityInstance>>,
 ) {
+	let glyphs = Glyphs {
+		mapping: &mapping,
+		input_mode: *input_mode,
+		device: active_gamepad.0.and_then(|gp| gamepads.name(gp)),
+	};
 	for instance in q_spawned_ldtk_entities
 		.iter()
 		.filter(|e| e.identifier == "Text")
 	{
 		if let Err(e) = (|| {
 			let source = instance.get_string_field("content")?;
+			let parts = parse_text(source)?;
 
 			commands.spawn((
-				FloatingText(source.clone()),
 				Text2dBundle {
 					text: Text::from_section(
-						display_for_keyboard(&source),
+						glyphs.render(&parts),
 						TextStyle {
 							font: asset_server.load("fonts/FiraSans-Bold.ttf"),
 							font_size: 50.0,
@@ -54,33 +132,34 @@ fn spawn_text(
 					.with_scale(Vec3::splat(1.0 / 50.0 * 0.6)),
 					..default()
 				},
+				FloatingText(parts),
 				Exit(AppState::Game),
 			));
 
 			Result::<_, Box<dyn Error>>::Ok(())
 		})() {
-			warn!("failed to spawn launchpad: {e}");
+			error!("failed to spawn text: {e}");
 		}
 	}
 }
 
-fn update_text(input_mode: Res<CurrentInputMode>, mut q_text: Query<(&mut Text, &FloatingText)>) {
-	if input_mode.is_changed() {
-		for (mut text, src) in &mut q_text {
-			match input_mode.as_ref() {
-				CurrentInputMode::Keyboard => text.sections[0].value = display_for_keyboard(&src.0),
-				CurrentInputMode::Gamepad => text.sections[0].value = display_for_gamepad(&src.0),
-			}
-		}
+/// Keep the text in sync with the bindings & the device being used
+fn update_text(
+	mapping: Res<InputMapping>,
+	input_mode: Res<CurrentInputMode>,
+	gamepads: Res<Gamepads>,
+	active_gamepad: Res<ActiveGamepad>,
+	mut q_text: Query<(&mut Text, &FloatingText)>,
+) {
+	if !(mapping.is_changed() || input_mode.is_changed() || active_gamepad.is_changed()) {
+		return;
+	}
+	let glyphs = Glyphs {
+		mapping: &mapping,
+		input_mode: *input_mode,
+		device: active_gamepad.0.and_then(|gp| gamepads.name(gp)),
+	};
+	for (mut text, src) in &mut q_text {
+		text.sections[0].value = glyphs.render(&src.0);
 	}
-}
-
-fn display_for_keyboard(input: &str) -> String {
-	input
-		.replace("<jump>", "Space")
-		.replace("<ground_pound>", "Down Arrow")
-}
-
-fn display_for_gamepad(input: &str) -> String {
-	input.replace("<jump>", "A").replace("<ground_pound>", "X")
 }
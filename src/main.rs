@@ -13,15 +13,20 @@ use bevy_hanabi::HanabiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::prelude::*;
 
+mod audio;
 mod game;
 mod input;
+mod input_display;
 mod leaderboard;
 mod level;
 mod menu;
 mod player;
 mod replay;
+mod savestate;
 mod settings;
 mod states;
+mod tas;
+mod video;
 
 // Pour débogguer l'ordre d'exécution des systèmes
 const DEBUG_SCHEDULE: bool = false;
@@ -67,6 +72,8 @@ fn main() {
 		})
 		// Particle effects
 		.add_plugin(HanabiPlugin)
+		// Music & sound effects
+		.add_plugin(audio::MixerPlugin)
 		// Physics
 		.insert_resource(RapierConfiguration {
 			gravity: -Vec2::Y * 80.0,
@@ -77,11 +84,13 @@ fn main() {
 			},
 			..default()
 		})
-		.add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1.0))
+		.add_plugin(RapierPhysicsPlugin::<level::one_way::OneWayHooks>::pixels_per_meter(1.0))
 		// User interface
 		.add_plugin(bevy_egui::EguiPlugin)
 		// settings
 		.add_plugin(settings::SettingsPlugin)
+		// Window mode & resolution
+		.add_plugin(video::VideoPlugin)
 		// input
 		.add_plugin(input::InputPlugin)
 		// Game state (menu, in-game, etc)
@@ -92,13 +101,19 @@ fn main() {
 		.add_plugin(level::LevelPlugin)
 		// Replays & ghosts
 		.add_plugin(replay::ReplayPlugin)
+		// Savestates (practice only)
+		.add_plugin(savestate::SavestatePlugin)
+		// Tool-assisted runs
+		.add_plugin(tas::TasPlugin)
+		// Overlay of the held actions
+		.add_plugin(input_display::InputDisplayPlugin)
 		// Main menu logic
 		.add_plugin(menu::MenuPlugin)
 		// Main game logic
 		.add_plugin(game::GamePlugin)
 		// Leaderboard view (menu after a successful run)
 		.add_plugin(leaderboard::LeaderboardPlugin)
-		.add_startup_systems((configure_egui, setup_music));
+		.add_startup_system(configure_egui);
 
 	#[cfg(debug_assertions)]
 	{
@@ -113,28 +128,6 @@ fn main() {
 	app.run();
 }
 
-/// Allows controlling the music
-#[derive(Resource)]
-pub struct MusicSink(Handle<AudioSink>);
-fn setup_music(
-	mut commands: Commands,
-	asset_server: Res<AssetServer>,
-	audio_sinks: Res<Assets<AudioSink>>,
-	audio: Res<Audio>,
-) {
-	let music = asset_server.load("sounds/music.ogg");
-	let mut music_sink = audio.play_with_settings(
-		music,
-		PlaybackSettings {
-			repeat: true,
-			volume: 0.0,
-			speed: 1.0,
-		},
-	);
-	music_sink.make_strong(&audio_sinks);
-	commands.insert_resource(MusicSink(music_sink));
-}
-
 /// UI styling
 fn configure_egui(mut contexts: EguiContexts) {
 	let ctx = contexts.ctx_mut();
@@ -0,0 +1,174 @@
+///
+/// Keyboard & gamepad navigation through menu widgets
+/// Screens describe their focusable widgets while building their UI, row by row,
+/// then `focus_navigation` moves the focus and activates widgets from the menu actions
+///
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use bevy_iced::{
+	iced::{
+		widget::{container, Button, Container},
+		Color, Element,
+	},
+	iced_wgpu::{Renderer, Theme},
+};
+
+use crate::input::Action;
+
+/// Something that can be focused in a menu
+pub enum FocusTarget<M> {
+	/// Sends the message when confirmed
+	Button(M),
+	/// Left & right change the value
+	Slider {
+		value: f32,
+		step: f32,
+		range: RangeInclusive<f32>,
+		on_change: fn(f32) -> M,
+	},
+}
+
+/// Focusable widgets of the screen being displayed, and which one is focused
+#[derive(Resource)]
+pub struct MenuFocus<M> {
+	/// Focused widget, as (row, column)
+	pub focused: (usize, usize),
+	rows: Vec<Vec<FocusTarget<M>>>,
+	back: Option<M>,
+}
+
+impl<M> Default for MenuFocus<M> {
+	fn default() -> Self {
+		Self {
+			focused: (0, 0),
+			rows: vec![],
+			back: None,
+		}
+	}
+}
+
+impl<M: Clone + 'static> MenuFocus<M> {
+	/// Starts a new row of focusable widgets
+	pub fn row(&mut self) {
+		self.rows.push(vec![]);
+	}
+
+	/// Forget the focusable widgets, so that nothing reacts to navigation during this frame
+	pub fn clear(&mut self) {
+		self.rows.clear();
+		self.back = None;
+	}
+
+	/// Message sent when going back from this screen
+	pub fn on_back(&mut self, message: M) {
+		self.back = Some(message);
+	}
+
+	/// Adds a widget to the current row, highlighted when it is focused
+	pub fn push<'a>(
+		&mut self,
+		target: FocusTarget<M>,
+		widget: impl Into<Element<'a, M, Renderer>>,
+	) -> Element<'a, M, Renderer> {
+		if self.rows.is_empty() {
+			self.row();
+		}
+		let row = self.rows.len() - 1;
+		let col = self.rows[row].len();
+		self.rows[row].push(target);
+
+		let container = Container::new(widget).padding(2.0);
+		match self.focused == (row, col) {
+			true => container
+				.style(focus_appearance as for<'b> fn(&'b Theme) -> container::Appearance)
+				.into(),
+			false => container.into(),
+		}
+	}
+
+	/// Adds a button sending the message when pressed or confirmed
+	pub fn button<'a>(
+		&mut self,
+		button: Button<'a, M, Renderer>,
+		message: M,
+	) -> Element<'a, M, Renderer> {
+		self.push(
+			FocusTarget::Button(message.clone()),
+			button.on_press(message),
+		)
+	}
+}
+
+fn focus_appearance(_theme: &Theme) -> container::Appearance {
+	container::Appearance {
+		border_radius: 5.0,
+		border_width: 2.0,
+		border_color: Color::from_rgb(1.0, 0.8, 0.2),
+		..default()
+	}
+}
+
+/// Moves the focus & activates the focused widget
+/// Runs after the screen was built, the focusable widgets are rebuilt every frame
+pub fn focus_navigation<M: Clone + Send + Sync + 'static>(
+	actions: Res<Input<Action>>,
+	mut focus: ResMut<MenuFocus<M>>,
+	mut messages: EventWriter<M>,
+) {
+	let rows = std::mem::take(&mut focus.rows);
+	let back = focus.back.take();
+	if rows.is_empty() {
+		return;
+	}
+
+	let (mut row, mut col) = focus.focused;
+	row = row.min(rows.len() - 1);
+	if actions.just_pressed(Action::MenuUp) {
+		row = row.saturating_sub(1);
+	}
+	if actions.just_pressed(Action::MenuDown) {
+		row = (row + 1).min(rows.len() - 1);
+	}
+	col = col.min(rows[row].len().saturating_sub(1));
+
+	match rows[row].get(col) {
+		// sliders use left & right for their value
+		Some(FocusTarget::Slider {
+			value,
+			step,
+			range,
+			on_change,
+		}) => {
+			if actions.just_pressed(Action::MenuLeft) {
+				messages.send(on_change((value - step).max(*range.start())));
+			}
+			if actions.just_pressed(Action::MenuRight) {
+				messages.send(on_change((value + step).min(*range.end())));
+			}
+		}
+		_ => {
+			if actions.just_pressed(Action::MenuLeft) {
+				col = col.saturating_sub(1);
+			}
+			if actions.just_pressed(Action::MenuRight) {
+				col = (col + 1).min(rows[row].len().saturating_sub(1));
+			}
+		}
+	}
+
+	if actions.just_pressed(Action::Confirm) {
+		if let Some(FocusTarget::Button(message)) = rows[row].get(col) {
+			messages.send(message.clone());
+		}
+	}
+	if actions.just_pressed(Action::Back) {
+		if let Some(message) = back {
+			messages.send(message);
+		}
+	}
+
+	if focus.focused != (row, col) {
+		focus.focused = (row, col);
+	}
+}
@@ -0,0 +1,123 @@
+///
+/// On-screen keyboard, to type the nickname without a physical keyboard
+///
+use bevy::ecs::prelude::*;
+use bevy_iced::{
+	iced::{
+		widget::{text, Button, Column, Row},
+		Alignment, Length,
+	},
+	IcedContext,
+};
+
+use super::{focus::MenuFocus, MenuState};
+use crate::leaderboard::Nickname;
+
+const KEY_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl-", "zxcvbnm_.!"];
+const MAX_NICKNAME_LEN: usize = 24;
+
+#[derive(Clone)]
+pub enum KeyboardMessage {
+	Type(char),
+	Space,
+	Delete,
+	Shift,
+	Done,
+}
+
+#[derive(Default)]
+pub struct OnScreenKeyboard {
+	shift: bool,
+}
+
+pub fn keyboard_update(
+	mut messages: EventReader<KeyboardMessage>,
+	mut menu_state: ResMut<MenuState>,
+	mut nickname: ResMut<Nickname>,
+) {
+	let MenuState::Keyboard(keyboard) = menu_state.as_mut() else {
+		return;
+	};
+
+	for msg in messages.iter() {
+		match msg {
+			KeyboardMessage::Type(c) => {
+				if nickname.0.chars().count() < MAX_NICKNAME_LEN {
+					nickname.0.push(*c);
+				}
+				// like on phones, shift only applies to the next letter
+				keyboard.shift = false;
+			}
+			KeyboardMessage::Space => {
+				if nickname.0.chars().count() < MAX_NICKNAME_LEN {
+					nickname.0.push(' ');
+				}
+			}
+			KeyboardMessage::Delete => {
+				nickname.0.pop();
+			}
+			KeyboardMessage::Shift => keyboard.shift = !keyboard.shift,
+			KeyboardMessage::Done => {
+				*menu_state = MenuState::Main;
+				return;
+			}
+		}
+	}
+}
+
+pub fn keyboard_ui(
+	mut ctx: IcedContext<KeyboardMessage>,
+	menu_state: Res<MenuState>,
+	nickname: Res<Nickname>,
+	mut focus: ResMut<MenuFocus<KeyboardMessage>>,
+) {
+	let MenuState::Keyboard(keyboard) = menu_state.as_ref() else {
+		return;
+	};
+	focus.on_back(KeyboardMessage::Done);
+
+	let mut keys = Column::new().align_items(Alignment::Center).spacing(4.0);
+	for key_row in KEY_ROWS {
+		focus.row();
+		let mut row = Row::new().spacing(4.0);
+		for c in key_row.chars() {
+			let c = match keyboard.shift {
+				true => c.to_ascii_uppercase(),
+				false => c,
+			};
+			row = row.push(focus.button(
+				Button::new(text(c).width(32.0)).padding(8.0),
+				KeyboardMessage::Type(c),
+			));
+		}
+		keys = keys.push(row);
+	}
+
+	focus.row();
+	let shift_label = match keyboard.shift {
+		true => "SHIFT",
+		false => "Shift",
+	};
+	keys = keys.push(
+		Row::new()
+			.spacing(4.0)
+			.push(focus.button(Button::new(shift_label), KeyboardMessage::Shift))
+			.push(focus.button(
+				Button::new(text("Space").width(192.0)),
+				KeyboardMessage::Space,
+			))
+			.push(focus.button(Button::new("Delete"), KeyboardMessage::Delete))
+			.push(focus.button(Button::new("Done"), KeyboardMessage::Done)),
+	);
+
+	ctx.display(
+		Column::new()
+			.align_items(Alignment::Center)
+			.width(Length::Fill)
+			.padding(64.0)
+			.spacing(32.0)
+			.push(text("Nickname").size(30.0))
+			.push(text(format!("{}_", nickname.0)).size(24.0))
+			.push(keys),
+	);
+}
@@ -1,9 +1,11 @@
+pub mod focus;
+mod keyboard;
 mod settings;
 
 use bevy::{
 	app::prelude::*,
 	ecs::prelude::*,
-	prelude::{Assets, AudioSink, AudioSinkPlayback, Camera2dBundle, Handle},
+	prelude::{Assets, Camera2dBundle, Handle},
 	utils::default,
 };
 use bevy_ecs_ldtk::{prelude::LdtkFields, LdtkAsset, LevelSelection, LevelSet, Respawn};
@@ -15,19 +17,29 @@ use bevy_iced::{
 	IcedContext, IcedPlugin,
 };
 
-use self::settings::{SettingsMenuState, SettingsUiMessage};
+use self::{
+	focus::{focus_navigation, FocusTarget, MenuFocus},
+	keyboard::{KeyboardMessage, OnScreenKeyboard},
+	settings::{SettingsMenuState, SettingsUiMessage},
+};
 use crate::{
+	audio::AudioSettings,
+	game::practice::PracticeMode,
 	leaderboard::{Leaderboard, Nickname},
+	settings::SaveSettings,
 	states::{AppState, Exit},
-	MusicSink,
+	tas::TasMode,
 };
 
 #[derive(Clone)]
 pub enum UiMessage {
 	EnterLevel(usize),
 	EnterSettings,
+	OpenKeyboard,
 	SetNickname(String),
 	SetMusicMuted(bool),
+	SetPracticeMode(bool),
+	SetTasMode(bool),
 }
 
 pub struct MenuPlugin;
@@ -37,11 +49,34 @@ impl Plugin for MenuPlugin {
 		app.add_plugin(IcedPlugin)
 			.add_event::<UiMessage>()
 			.add_event::<SettingsUiMessage>()
+			.add_event::<KeyboardMessage>()
 			.insert_resource(MenuState::default())
+			.insert_resource(MenuFocus::<UiMessage>::default())
+			.insert_resource(MenuFocus::<SettingsUiMessage>::default())
+			.insert_resource(MenuFocus::<KeyboardMessage>::default())
 			.add_system(setup.in_schedule(OnEnter(AppState::Menu)))
-			.add_systems((ui_system, ui_update).distributive_run_if(in_state(AppState::Menu)))
 			.add_systems(
-				(settings::settings_update, settings::settings_ui)
+				(
+					ui_system,
+					focus_navigation::<UiMessage>.after(ui_system),
+					ui_update,
+				)
+					.distributive_run_if(in_state(AppState::Menu)),
+			)
+			.add_systems(
+				(
+					settings::settings_update,
+					settings::settings_ui,
+					focus_navigation::<SettingsUiMessage>.after(settings::settings_ui),
+				)
+					.distributive_run_if(in_state(AppState::Menu)),
+			)
+			.add_systems(
+				(
+					keyboard::keyboard_update,
+					keyboard::keyboard_ui,
+					focus_navigation::<KeyboardMessage>.after(keyboard::keyboard_ui),
+				)
 					.distributive_run_if(in_state(AppState::Menu)),
 			);
 	}
@@ -55,8 +90,10 @@ fn ui_update(
 	mut level_selection: ResMut<LevelSelection>,
 	mut nickname: ResMut<Nickname>,
 	q_ldtk_world: Query<Entity, With<LevelSet>>,
-	audio_sinks: Res<Assets<AudioSink>>,
-	music: Res<MusicSink>,
+	mut audio: ResMut<AudioSettings>,
+	mut ev_save_settings: EventWriter<SaveSettings>,
+	mut practice_mode: ResMut<PracticeMode>,
+	mut tas_mode: ResMut<TasMode>,
 ) {
 	let world = q_ldtk_world.single();
 
@@ -70,15 +107,21 @@ fn ui_update(
 			UiMessage::EnterSettings => {
 				*state = MenuState::Settings(SettingsMenuState::default());
 			}
+			UiMessage::OpenKeyboard => {
+				*state = MenuState::Keyboard(OnScreenKeyboard::default());
+			}
 			UiMessage::SetNickname(s) => nickname.0 = s.clone(),
 			UiMessage::SetMusicMuted(muted) => {
-				if let Some(sink) = audio_sinks.get(&music.0) {
-					if *muted {
-						sink.pause();
-					} else {
-						sink.play();
-					}
-				}
+				audio.music_muted = *muted;
+				ev_save_settings.send(SaveSettings);
+			}
+			UiMessage::SetPracticeMode(enabled) => {
+				practice_mode.0 = *enabled;
+				tas_mode.0 &= !*enabled;
+			}
+			UiMessage::SetTasMode(enabled) => {
+				tas_mode.0 = *enabled;
+				practice_mode.0 &= !*enabled;
 			}
 		}
 	}
@@ -91,8 +134,10 @@ fn ui_system(
 	q_ldtk_world: Query<&Handle<LdtkAsset>, With<LevelSet>>,
 	ldtk_asset: Res<Assets<LdtkAsset>>,
 	nickname: Res<Nickname>,
-	audio_sinks: Res<Assets<AudioSink>>,
-	music: Res<MusicSink>,
+	audio: Res<AudioSettings>,
+	practice_mode: Res<PracticeMode>,
+	tas_mode: Res<TasMode>,
+	mut focus: ResMut<MenuFocus<UiMessage>>,
 ) {
 	let MenuState::Main = *state else {
 		return;
@@ -101,6 +146,15 @@ fn ui_system(
 	let ldtk_handle = q_ldtk_world.single();
 
 	let title = text(format!("S P E E E D")).size(38.0);
+
+	// Confirming the nickname field opens the on-screen keyboard
+	focus.row();
+	let nickname_input = focus.push(
+		FocusTarget::Button(UiMessage::OpenKeyboard),
+		TextInput::new("Nickname", &nickname.0).on_input(|s| UiMessage::SetNickname(s)),
+	);
+
+	focus.row();
 	let mut levels = Row::new().spacing(16.0);
 	for (i, level) in ldtk_asset
 		.get(ldtk_handle)
@@ -112,48 +166,64 @@ fn ui_system(
 	{
 		let name = level.get_string_field("name").unwrap();
 		levels = levels.push(
-			Button::new(
-				Column::new()
-					.push(text(format!("Level {i}")))
-					.push(text(name))
-					.push("Best time:")
-					.push(
-						if let Some((name, score)) = leaderboard.get_scores(i).first() {
-							text(format!("{name}: {score}").as_str())
-						} else {
-							text("no score yet")
-						},
-					),
-			)
-			.on_press(UiMessage::EnterLevel(i))
-			.padding(16.0),
+			focus.button(
+				Button::new(
+					Column::new()
+						.push(text(format!("Level {i}")))
+						.push(text(name))
+						.push("Best time:")
+						.push(
+							if let Some((name, score)) = leaderboard.get_scores(i).first() {
+								text(format!("{name}: {score}").as_str())
+							} else {
+								text("no score yet")
+							},
+						),
+				)
+				.padding(16.0),
+				UiMessage::EnterLevel(i),
+			),
 		);
 	}
 
-	let mut extra_buttons = Row::new();
+	focus.row();
+	let settings_button = focus.button(Button::new("Settings"), UiMessage::EnterSettings);
+
+	focus.row();
+	let mut extra_buttons = Row::new().spacing(16.0);
+
+	// Practice mode: checkpoints are enabled, scores are not submitted
+	extra_buttons = extra_buttons.push(match practice_mode.0 {
+		false => focus.button(
+			Button::new("Practice mode: off"),
+			UiMessage::SetPracticeMode(true),
+		),
+		true => focus.button(
+			Button::new("Practice mode: on"),
+			UiMessage::SetPracticeMode(false),
+		),
+	});
+
+	// Tool-assisted runs: frame advance & input timeline
+	extra_buttons = extra_buttons.push(match tas_mode.0 {
+		false => focus.button(Button::new("TAS mode: off"), UiMessage::SetTasMode(true)),
+		true => focus.button(Button::new("TAS mode: on"), UiMessage::SetTasMode(false)),
+	});
 
 	// Music mute/unmute
-	if let Some(sink) = audio_sinks.get(&music.0) {
-		match sink.is_paused() {
-			false => {
-				extra_buttons = extra_buttons
-					.push(Button::new("Mute music").on_press(UiMessage::SetMusicMuted(true)))
-			}
-			true => {
-				extra_buttons = extra_buttons
-					.push(Button::new("Unmute music").on_press(UiMessage::SetMusicMuted(false)))
-			}
-		}
-	}
+	extra_buttons = extra_buttons.push(match audio.music_muted {
+		false => focus.button(Button::new("Mute music"), UiMessage::SetMusicMuted(true)),
+		true => focus.button(Button::new("Unmute music"), UiMessage::SetMusicMuted(false)),
+	});
 
 	let main_col = Column::new()
 		.align_items(Alignment::Center)
 		.padding(64.0)
 		.spacing(32.0)
 		.push(title)
-		.push(TextInput::new("Nickname", &nickname.0).on_input(|s| UiMessage::SetNickname(s)))
+		.push(nickname_input)
 		.push(Scrollable::new(levels.padding([32.0, 0.0])).horizontal_scroll(default()))
-		.push(Button::new("Settings").on_press(UiMessage::EnterSettings))
+		.push(settings_button)
 		.push(extra_buttons);
 
 	ctx.display(main_col);
@@ -164,6 +234,7 @@ pub enum MenuState {
 	#[default]
 	Main,
 	Settings(SettingsMenuState),
+	Keyboard(OnScreenKeyboard),
 }
 
 fn setup(mut commands: Commands) {
@@ -1,23 +1,70 @@
-use bevy::ecs::prelude::*;
+use bevy::ecs::{prelude::*, system::SystemParam};
 use bevy_iced::{
 	iced::{
-		widget::{text, Button, Column, Row},
+		color,
+		widget::{text, Button, Column, Row, Scrollable, Slider},
 		Alignment, Length,
 	},
 	IcedContext,
 };
 use bevy_input::prelude::*;
 
-use super::MenuState;
+use super::{
+	focus::{FocusTarget, MenuFocus},
+	MenuState,
+};
 use crate::{
-	input::{Action, ButtonOrAxis, InputMapping, JOYSTICK_THRESHOLD},
+	audio::AudioSettings,
+	game::{intro::IntroSettings, juice::MotionSettings},
+	input::{gamepad::ActiveGamepad, Action, ButtonOrAxis, InputMapping},
+	input_display::{Corner, InputDisplay},
+	leaderboard::{Leaderboard, Nickname},
+	player::skin::{SkinSettings, SKINS},
+	replay::GhostSettings,
 	settings::SaveSettings,
+	video::{
+		DisplayMode, GraphicsQuality, QualityPreset, VideoSettings, EFFECT_CAPACITIES, FRAME_CAPS,
+		RESOLUTIONS, UI_SCALES,
+	},
 };
 
 #[derive(Clone)]
 pub enum SettingsUiMessage {
-	EditMapping(Action, InputMode),
+	/// Edit the nth binding of an action (past the end to add one)
+	EditMapping(Action, InputMode, usize),
 	ResetMappings,
+	/// Gamepad that drives the player, by name (the last one used if None)
+	SelectGamepad(Option<String>),
+	SetAnalogMovement(bool),
+	SetDeadzone(f32),
+	SetPressThreshold(f32),
+	SetReleaseThreshold(f32),
+	SetInputDisplay(bool),
+	SetInputDisplayCorner(Corner),
+	SetMasterVolume(f32),
+	SetMusicVolume(f32),
+	SetSfxVolume(f32),
+	SetGhost(bool),
+	SetGhostOpacity(f32),
+	/// Skin of the current profile, by index in `SKINS`
+	SetSkin(usize),
+	SetIntroPan(bool),
+	SetCountdown(bool),
+	SetRestartCountdown(bool),
+	SetReducedMotion(bool),
+	SetDisplayMode(DisplayMode),
+	SetResolution((u32, u32)),
+	SetVsync(bool),
+	SetUiScale(f32),
+	SetFrameCap(Option<u32>),
+	SetQualityPreset(QualityPreset),
+	SetHdr(bool),
+	SetBloom(bool),
+	SetTonemapping(bool),
+	SetParticleDensity(f32),
+	SetEffectCapacity(u32),
+	/// Save the settings and go back to the main menu
+	Back,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -28,13 +75,47 @@ pub enum InputMode {
 
 #[derive(Default)]
 pub struct SettingsMenuState {
-	editing_action: Option<(Action, InputMode)>,
+	editing_action: Option<(Action, InputMode, usize)>,
+}
+
+/// Replaces the binding at `idx`, or adds it if `idx` is past the end
+fn set_binding<T: PartialEq>(bindings: &mut Vec<T>, idx: usize, binding: T) {
+	if bindings.contains(&binding) {
+		return;
+	}
+	match bindings.get_mut(idx) {
+		Some(b) => *b = binding,
+		None => bindings.push(binding),
+	}
+}
+
+/// The choice following `current`, wrapping around (the first one if `current` isn't a choice)
+fn cycle<T: PartialEq + Copy>(choices: &[T], current: T) -> T {
+	let idx = choices.iter().position(|c| *c == current);
+	choices[idx.map_or(0, |i| (i + 1) % choices.len())]
+}
+
+/// Removes the binding at `idx`, if it exists
+fn remove_binding<T>(bindings: &mut Vec<T>, idx: usize) {
+	if idx < bindings.len() {
+		bindings.remove(idx);
+	}
 }
 
 pub fn settings_update(
 	mut messages: EventReader<SettingsUiMessage>,
 	mut menu_state: ResMut<MenuState>,
 	mut mappings: ResMut<InputMapping>,
+	mut input_display: ResMut<InputDisplay>,
+	mut audio: ResMut<AudioSettings>,
+	mut ghosts: ResMut<GhostSettings>,
+	mut skins: ResMut<SkinSettings>,
+	nickname: Res<Nickname>,
+	mut intro: ResMut<IntroSettings>,
+	mut motion: ResMut<MotionSettings>,
+	mut video: ResMut<VideoSettings>,
+	mut graphics: ResMut<GraphicsQuality>,
+	mut ev_save_settings: EventWriter<SaveSettings>,
 ) {
 	let MenuState::Settings(state) = menu_state.as_mut() else {
 		return;
@@ -42,16 +123,97 @@ pub fn settings_update(
 
 	for msg in messages.iter() {
 		match msg {
-			SettingsUiMessage::EditMapping(action, input_mode) => {
-				state.editing_action = Some((*action, *input_mode));
+			SettingsUiMessage::Back => {
+				ev_save_settings.send(SaveSettings);
+				*menu_state = MenuState::Main;
+				return;
+			}
+			SettingsUiMessage::EditMapping(action, input_mode, idx) => {
+				state.editing_action = Some((*action, *input_mode, *idx));
 			}
 			SettingsUiMessage::ResetMappings => {
-				*mappings = InputMapping::default();
+				*mappings = InputMapping {
+					preferred_gamepad: mappings.preferred_gamepad.take(),
+					..InputMapping::default()
+				};
+			}
+			SettingsUiMessage::SelectGamepad(name) => {
+				mappings.preferred_gamepad = name.clone();
+			}
+			SettingsUiMessage::SetAnalogMovement(enabled) => {
+				mappings.sticks.analog_movement = *enabled;
+			}
+			SettingsUiMessage::SetDeadzone(value) => mappings.sticks.deadzone = *value,
+			// the release threshold must stay below the press threshold
+			SettingsUiMessage::SetPressThreshold(value) => {
+				mappings.sticks.press_threshold = *value;
+				mappings.sticks.release_threshold = mappings.sticks.release_threshold.min(*value);
+			}
+			SettingsUiMessage::SetReleaseThreshold(value) => {
+				mappings.sticks.release_threshold = value.min(mappings.sticks.press_threshold);
+			}
+			SettingsUiMessage::SetInputDisplay(enabled) => input_display.enabled = *enabled,
+			SettingsUiMessage::SetInputDisplayCorner(corner) => input_display.corner = *corner,
+			SettingsUiMessage::SetMasterVolume(value) => audio.master = *value,
+			SettingsUiMessage::SetMusicVolume(value) => audio.music = *value,
+			SettingsUiMessage::SetSfxVolume(value) => audio.sfx = *value,
+			SettingsUiMessage::SetGhost(enabled) => ghosts.enabled = *enabled,
+			SettingsUiMessage::SetGhostOpacity(value) => ghosts.opacity = *value,
+			SettingsUiMessage::SetSkin(idx) => {
+				skins
+					.skins
+					.insert(nickname.0.clone(), SKINS[*idx].name.to_owned());
+			}
+			SettingsUiMessage::SetIntroPan(enabled) => intro.pan = *enabled,
+			SettingsUiMessage::SetCountdown(enabled) => intro.countdown = *enabled,
+			SettingsUiMessage::SetRestartCountdown(enabled) => intro.restart_countdown = *enabled,
+			SettingsUiMessage::SetReducedMotion(enabled) => motion.reduced_motion = *enabled,
+			SettingsUiMessage::SetDisplayMode(mode) => video.mode = *mode,
+			SettingsUiMessage::SetResolution(resolution) => video.resolution = *resolution,
+			SettingsUiMessage::SetVsync(enabled) => video.vsync = *enabled,
+			SettingsUiMessage::SetUiScale(scale) => video.ui_scale = *scale,
+			SettingsUiMessage::SetFrameCap(cap) => video.frame_cap = *cap,
+			SettingsUiMessage::SetQualityPreset(preset) => {
+				*graphics = GraphicsQuality::preset(*preset);
+			}
+			// editing a setting leaves the preset
+			SettingsUiMessage::SetHdr(enabled) => {
+				graphics.hdr = *enabled;
+				graphics.preset = QualityPreset::Custom;
+			}
+			SettingsUiMessage::SetBloom(enabled) => {
+				graphics.bloom = *enabled;
+				graphics.preset = QualityPreset::Custom;
+			}
+			SettingsUiMessage::SetTonemapping(enabled) => {
+				graphics.tonemapping = *enabled;
+				graphics.preset = QualityPreset::Custom;
+			}
+			SettingsUiMessage::SetParticleDensity(value) => {
+				graphics.particle_density = *value;
+				graphics.preset = QualityPreset::Custom;
+			}
+			SettingsUiMessage::SetEffectCapacity(capacity) => {
+				graphics.effect_capacity = *capacity;
+				graphics.preset = QualityPreset::Custom;
 			}
 		}
 	}
 }
 
+/// Settings shown in the menu, besides the input mappings
+#[derive(SystemParam)]
+pub struct ShownSettings<'w> {
+	input_display: Res<'w, InputDisplay>,
+	audio: Res<'w, AudioSettings>,
+	ghosts: Res<'w, GhostSettings>,
+	skins: Res<'w, SkinSettings>,
+	intro: Res<'w, IntroSettings>,
+	motion: Res<'w, MotionSettings>,
+	video: Res<'w, VideoSettings>,
+	graphics: Res<'w, GraphicsQuality>,
+}
+
 pub fn settings_ui(
 	mut ctx: IcedContext<SettingsUiMessage>,
 	mut menu_state: ResMut<MenuState>,
@@ -59,82 +221,456 @@ pub fn settings_ui(
 	keys: Res<Input<KeyCode>>,
 	gamepad_axes: Res<Axis<GamepadAxis>>,
 	buttons: Res<Input<GamepadButton>>,
-	mut ev_save_settings: EventWriter<SaveSettings>,
+	gamepads: Res<Gamepads>,
+	active_gamepad: Res<ActiveGamepad>,
+	settings: ShownSettings,
+	leaderboard: Res<Leaderboard>,
+	nickname: Res<Nickname>,
+	mut focus: ResMut<MenuFocus<SettingsUiMessage>>,
 ) {
+	let ShownSettings {
+		input_display,
+		audio,
+		ghosts,
+		skins,
+		intro,
+		motion,
+		video,
+		graphics,
+	} = settings;
 	let MenuState::Settings(state) = menu_state.as_mut() else {
 		return;
 	};
+	focus.on_back(SettingsUiMessage::Back);
+
+	// gamepad bindings are edited for the active gamepad
+	let device = active_gamepad.0.and_then(|gp| gamepads.name(gp));
+	let from_active = |gp: Gamepad| active_gamepad.0.is_none() || active_gamepad.0 == Some(gp);
+
+	let mut choices = vec![None];
+	choices.extend(
+		gamepads
+			.iter()
+			.filter_map(|gp| gamepads.name(gp).map(str::to_owned).map(Some)),
+	);
+	let current = choices
+		.iter()
+		.position(|c| *c == mappings.preferred_gamepad)
+		.unwrap_or(0);
+	let next_choice = choices[(current + 1) % choices.len()].clone();
+	focus.row();
+	let gamepad_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(focus.button(
+			Button::new(text(match &mappings.preferred_gamepad {
+				Some(name) => format!("Gamepad: {name}"),
+				None => "Gamepad: last used".to_owned(),
+			})),
+			SettingsUiMessage::SelectGamepad(next_choice),
+		))
+		.push(text(match device {
+			Some(name) => format!("Gamepad bindings for {name}"),
+			None => "Gamepad bindings for all gamepads".to_owned(),
+		}));
 
-	let mut mappings_col = Column::new().align_items(Alignment::Center);
-	for (action, mapping) in &mappings.0 {
-		let mut row = Row::new().push(text(format!("{:?}", action)).width(128.0));
-		row = row.push(
-			if state.editing_action == Some((*action, InputMode::Keyboard)) {
-				Button::new("?")
+	let mut mappings_col = Column::new().align_items(Alignment::Center).spacing(4.0);
+	for (action, mapping) in &mappings.actions {
+		focus.row();
+		let mut binding_button = |label: String, input_mode: InputMode, idx: usize| {
+			let message = SettingsUiMessage::EditMapping(*action, input_mode, idx);
+			if state.editing_action == Some((*action, input_mode, idx)) {
+				focus.push(FocusTarget::Button(message), Button::new("?"))
 			} else {
-				Button::new(text(format!("{:?}", mapping.key)))
-					.on_press(SettingsUiMessage::EditMapping(*action, InputMode::Keyboard))
+				focus.button(Button::new(text(label)), message)
 			}
-			.width(128.0),
+		};
+
+		let mut keys_row = Row::new().spacing(4.0).width(320.0);
+		for (idx, key) in mapping.keys.iter().enumerate() {
+			keys_row = keys_row.push(binding_button(format!("{key:?}"), InputMode::Keyboard, idx));
+		}
+		keys_row = keys_row.push(binding_button(
+			"+".to_owned(),
+			InputMode::Keyboard,
+			mapping.keys.len(),
+		));
+
+		let mut buttons_row = Row::new().spacing(4.0).width(384.0);
+		let gamepad_bindings = mappings.buttons(*action, device);
+		for (idx, button_or_axis) in gamepad_bindings.iter().enumerate() {
+			buttons_row = buttons_row.push(binding_button(
+				format!("{button_or_axis}"),
+				InputMode::Gamepad,
+				idx,
+			));
+		}
+		buttons_row = buttons_row.push(binding_button(
+			"+".to_owned(),
+			InputMode::Gamepad,
+			gamepad_bindings.len(),
+		));
+
+		mappings_col = mappings_col.push(
+			Row::new()
+				.spacing(16.0)
+				.push(text(format!("{:?}", action)).width(128.0))
+				.push(keys_row)
+				.push(buttons_row),
 		);
-		row = row.push(
-			if state.editing_action == Some((*action, InputMode::Gamepad)) {
-				Button::new("?")
-			} else {
-				Button::new(text(format!("{:?}", mapping.button_or_axis)))
-					.on_press(SettingsUiMessage::EditMapping(*action, InputMode::Gamepad))
-			}
-			.width(192.0),
+	}
+	if state.editing_action.is_some() {
+		mappings_col = mappings_col.push(text(
+			"Press a key or button (Delete to remove the binding, Escape to cancel)",
+		));
+	}
+
+	// The same binding triggering several actions is most likely a mistake
+	for (binding, actions) in mappings.conflicts(device) {
+		mappings_col = mappings_col.push(
+			text(format!("Warning: {binding} is bound to {actions:?}")).style(color!(0xFF0000)),
 		);
-		mappings_col = mappings_col.push(row);
 	}
 
-	ctx.display(
+	let sticks = &mappings.sticks;
+	let slider_row = |focus: &mut MenuFocus<SettingsUiMessage>,
+	                  label: &str,
+	                  value: f32,
+	                  on_change: fn(f32) -> SettingsUiMessage| {
+		focus.row();
+		let slider = Slider::new(0.0..=1.0, value, on_change)
+			.step(0.05)
+			.width(192.0);
+		let target = FocusTarget::Slider {
+			value,
+			step: 0.05,
+			range: 0.0..=1.0,
+			on_change,
+		};
+		Row::new()
+			.spacing(16.0)
+			.align_items(Alignment::Center)
+			.push(text(label).width(192.0))
+			.push(focus.push(target, slider))
+			.push(text(format!("{value:.2}")).width(64.0))
+	};
+	focus.row();
+	let sticks_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(match sticks.analog_movement {
+			false => focus.button(
+				Button::new("Analog movement: off"),
+				SettingsUiMessage::SetAnalogMovement(true),
+			),
+			true => focus.button(
+				Button::new("Analog movement: on"),
+				SettingsUiMessage::SetAnalogMovement(false),
+			),
+		})
+		.push(slider_row(
+			&mut focus,
+			"Stick deadzone",
+			sticks.deadzone,
+			SettingsUiMessage::SetDeadzone,
+		))
+		.push(slider_row(
+			&mut focus,
+			"Press threshold",
+			sticks.press_threshold,
+			SettingsUiMessage::SetPressThreshold,
+		))
+		.push(slider_row(
+			&mut focus,
+			"Release threshold",
+			sticks.release_threshold,
+			SettingsUiMessage::SetReleaseThreshold,
+		));
+
+	// Overlay of the held actions, useful when recording or reviewing runs
+	focus.row();
+	let display_row = Row::new()
+		.spacing(16.0)
+		.push(match input_display.enabled {
+			false => focus.button(
+				Button::new("Input display: off"),
+				SettingsUiMessage::SetInputDisplay(true),
+			),
+			true => focus.button(
+				Button::new("Input display: on"),
+				SettingsUiMessage::SetInputDisplay(false),
+			),
+		})
+		.push(focus.button(
+			Button::new(text(format!("Position: {}", input_display.corner))),
+			SettingsUiMessage::SetInputDisplayCorner(input_display.corner.next()),
+		));
+
+	let audio_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(slider_row(
+			&mut focus,
+			"Master volume",
+			audio.master,
+			SettingsUiMessage::SetMasterVolume,
+		))
+		.push(slider_row(
+			&mut focus,
+			"Music volume",
+			audio.music,
+			SettingsUiMessage::SetMusicVolume,
+		))
+		.push(slider_row(
+			&mut focus,
+			"Sound effects volume",
+			audio.sfx,
+			SettingsUiMessage::SetSfxVolume,
+		));
+
+	// Ghost of the best run on the level
+	focus.row();
+	let ghosts_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(match ghosts.enabled {
+			false => focus.button(Button::new("Ghost: off"), SettingsUiMessage::SetGhost(true)),
+			true => focus.button(Button::new("Ghost: on"), SettingsUiMessage::SetGhost(false)),
+		})
+		.push(slider_row(
+			&mut focus,
+			"Ghost opacity",
+			ghosts.opacity,
+			SettingsUiMessage::SetGhostOpacity,
+		));
+
+	let toggle = |focus: &mut MenuFocus<SettingsUiMessage>,
+	              label: &str,
+	              enabled: bool,
+	              message: fn(bool) -> SettingsUiMessage| {
+		let state = match enabled {
+			true => "on",
+			false => "off",
+		};
+		focus.button(
+			Button::new(text(format!("{label}: {state}"))),
+			message(!enabled),
+		)
+	};
+
+	// Skin of the current profile, cycling through the unlocked ones
+	focus.row();
+	let skin = skins.skin_of(&leaderboard, &nickname.0);
+	let unlocked: Vec<usize> = (0..SKINS.len())
+		.filter(|idx| SKINS[*idx].unlocked(&leaderboard, &nickname.0))
+		.collect();
+	let current = SKINS.iter().position(|s| s.name == skin.name).unwrap_or(0);
+	let mut skin_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(focus.button(
+			Button::new(text(format!("Skin: {}", skin.name))),
+			SettingsUiMessage::SetSkin(cycle(&unlocked, current)),
+		));
+	if let Some(locked) = SKINS
+		.iter()
+		.find(|s| !s.unlocked(&leaderboard, &nickname.0))
+	{
+		skin_col = skin_col.push(text(format!("Next skin: {}", locked.unlock)).size(16.0));
+	}
+
+	// What is shown before the run starts
+	focus.row();
+	let intro_row = Row::new()
+		.spacing(16.0)
+		.push(toggle(
+			&mut focus,
+			"Level preview",
+			intro.pan,
+			SettingsUiMessage::SetIntroPan,
+		))
+		.push(toggle(
+			&mut focus,
+			"Countdown",
+			intro.countdown,
+			SettingsUiMessage::SetCountdown,
+		))
+		.push(toggle(
+			&mut focus,
+			"Countdown on restart",
+			intro.restart_countdown,
+			SettingsUiMessage::SetRestartCountdown,
+		));
+
+	// Screen shake & hit-stop
+	focus.row();
+	let motion_button = toggle(
+		&mut focus,
+		"Reduced motion",
+		motion.reduced_motion,
+		SettingsUiMessage::SetReducedMotion,
+	);
+
+	focus.row();
+	let (width, height) = video.resolution;
+	let video_row_1 = Row::new()
+		.spacing(16.0)
+		.push(focus.button(
+			Button::new(text(format!("Window: {}", video.mode))),
+			SettingsUiMessage::SetDisplayMode(video.mode.next()),
+		))
+		.push(focus.button(
+			Button::new(text(format!("Resolution: {width}x{height}"))),
+			SettingsUiMessage::SetResolution(cycle(&RESOLUTIONS, video.resolution)),
+		))
+		.push(match video.vsync {
+			false => focus.button(Button::new("VSync: off"), SettingsUiMessage::SetVsync(true)),
+			true => focus.button(Button::new("VSync: on"), SettingsUiMessage::SetVsync(false)),
+		});
+	focus.row();
+	let video_row_2 = Row::new()
+		.spacing(16.0)
+		.push(focus.button(
+			Button::new(text(format!("UI scale: {:.0}%", video.ui_scale * 100.0))),
+			SettingsUiMessage::SetUiScale(cycle(&UI_SCALES, video.ui_scale)),
+		))
+		.push(focus.button(
+			Button::new(text(match video.frame_cap {
+				Some(fps) => format!("Frame cap: {fps} FPS"),
+				None => "Frame cap: none".to_owned(),
+			})),
+			SettingsUiMessage::SetFrameCap(cycle(&FRAME_CAPS, video.frame_cap)),
+		));
+	let video_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(video_row_1)
+		.push(video_row_2);
+
+	// Graphics quality, applied from the next level
+	focus.row();
+	let graphics_row_1 = Row::new()
+		.spacing(16.0)
+		.push(focus.button(
+			Button::new(text(format!("Quality: {}", graphics.preset))),
+			SettingsUiMessage::SetQualityPreset(graphics.preset.next()),
+		))
+		.push(focus.button(
+			Button::new(text(format!(
+				"Effect capacity: {}",
+				graphics.effect_capacity
+			))),
+			SettingsUiMessage::SetEffectCapacity(cycle(
+				&EFFECT_CAPACITIES,
+				graphics.effect_capacity,
+			)),
+		));
+	focus.row();
+	let graphics_row_2 = Row::new()
+		.spacing(16.0)
+		.push(toggle(
+			&mut focus,
+			"HDR",
+			graphics.hdr,
+			SettingsUiMessage::SetHdr,
+		))
+		.push(toggle(
+			&mut focus,
+			"Bloom",
+			graphics.bloom,
+			SettingsUiMessage::SetBloom,
+		))
+		.push(toggle(
+			&mut focus,
+			"Tonemapping",
+			graphics.tonemapping,
+			SettingsUiMessage::SetTonemapping,
+		));
+	let graphics_col = Column::new()
+		.align_items(Alignment::Center)
+		.spacing(8.0)
+		.push(graphics_row_1)
+		.push(graphics_row_2)
+		.push(slider_row(
+			&mut focus,
+			"Particle density",
+			graphics.particle_density,
+			SettingsUiMessage::SetParticleDensity,
+		));
+
+	focus.row();
+	let reset_button = focus.button(
+		Button::new("Reset to defaults"),
+		SettingsUiMessage::ResetMappings,
+	);
+
+	// The settings don't fit on small screens
+	ctx.display(Scrollable::new(
 		Column::new()
 			.align_items(Alignment::Center)
 			.width(Length::Fill)
 			.padding(64.0)
 			.spacing(32.0)
 			.push(text("Settings").size(30.0))
+			.push(gamepad_col)
 			.push(mappings_col)
-			.push(Button::new("Reset to defaults").on_press(SettingsUiMessage::ResetMappings)),
-	);
+			.push(sticks_col)
+			.push(display_row)
+			.push(audio_col)
+			.push(ghosts_col)
+			.push(skin_col)
+			.push(intro_row)
+			.push(motion_button)
+			.push(video_col)
+			.push(graphics_col)
+			.push(reset_button),
+	));
+
+	if let Some((action, input_mode, idx)) = state.editing_action {
+		// keys & buttons pressed while rebinding don't navigate the menu
+		focus.clear();
 
-	if let Some((action, input_mode)) = state.editing_action.clone() {
+		let threshold = mappings.sticks.press_threshold;
+		if keys.just_pressed(KeyCode::Escape) {
+			state.editing_action = None;
+			return;
+		}
+		if keys.just_pressed(KeyCode::Delete) {
+			match input_mode {
+				InputMode::Keyboard => {
+					remove_binding(&mut mappings.actions.get_mut(&action).unwrap().keys, idx)
+				}
+				InputMode::Gamepad => remove_binding(mappings.buttons_mut(action, device), idx),
+			}
+			state.editing_action = None;
+			return;
+		}
 		match input_mode {
 			InputMode::Keyboard => {
 				if let Some(key) = keys.get_just_pressed().next() {
-					let mapping = mappings.0.get_mut(&action).unwrap();
-					mapping.key = *key;
+					let mapping = mappings.actions.get_mut(&action).unwrap();
+					set_binding(&mut mapping.keys, idx, *key);
 					state.editing_action = None;
 				}
 			}
 			InputMode::Gamepad => {
-				if let Some(btn) = buttons.get_just_pressed().next() {
-					let mapping = mappings.0.get_mut(&action).unwrap();
-					mapping.button_or_axis = ButtonOrAxis::Button(btn.button_type);
+				let bindings = mappings.buttons_mut(action, device);
+				if let Some(btn) = buttons.get_just_pressed().find(|b| from_active(b.gamepad)) {
+					set_binding(bindings, idx, ButtonOrAxis::Button(btn.button_type));
 					state.editing_action = None;
 				}
-				for axis in gamepad_axes.devices() {
+				for axis in gamepad_axes.devices().filter(|a| from_active(a.gamepad)) {
 					let value = gamepad_axes.get(*axis).unwrap();
-					if value > JOYSTICK_THRESHOLD {
-						let mapping = mappings.0.get_mut(&action).unwrap();
-						mapping.button_or_axis = ButtonOrAxis::Axis(axis.axis_type, false);
+					if value > threshold {
+						set_binding(bindings, idx, ButtonOrAxis::Axis(axis.axis_type, false));
 						state.editing_action = None;
 					}
-					if value < -JOYSTICK_THRESHOLD {
-						let mapping = mappings.0.get_mut(&action).unwrap();
-						mapping.button_or_axis = ButtonOrAxis::Axis(axis.axis_type, true);
+					if value < -threshold {
+						set_binding(bindings, idx, ButtonOrAxis::Axis(axis.axis_type, true));
 						state.editing_action = None;
 					}
 				}
 			}
 		}
-	} else {
-		if keys.just_pressed(KeyCode::Escape) {
-			ev_save_settings.send(SaveSettings);
-			*menu_state = MenuState::Main;
-			return;
-		}
 	}
 }
@@ -2,31 +2,51 @@
 /// - a root entity
 ///     - a ground sensor
 ///     - a wall sensor
+///     - a sprite
+///     - a trail
 ///
-use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-	game::Restart,
-	input::{self, Action},
-	level::RestoresJump,
+	game::{GameplayEvent, Restart},
+	input::{self, Action, AnalogActions},
+	leaderboard::{Leaderboard, Nickname},
+	level::{
+		material::{MaterialTable, Surface},
+		one_way::{self, OneWayPlatform},
+		slope::SlopeCollider,
+		RestoresJump,
+	},
 	states::{AppState, Exit},
+	tas::StepDelta,
 };
 
+pub mod skin;
+pub mod visuals;
+
+use self::skin::{PlayerSkin, SkinSettings};
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
 	fn build(&self, app: &mut App) {
 		app.register_type::<Player>()
 			.add_event::<SpawnPlayer>()
+			.add_startup_system(visuals::load_player_atlas)
 			.add_systems(
 				(
 					player_spawn,
 					player_on_ground,
 					player_jumps,
 					player_controls.after(player_on_ground).after(player_jumps),
-					player_render,
+					player_footsteps.after(player_on_ground),
 					player_restart,
+					visuals::visuals_spawn,
+					visuals::player_animate.after(player_controls),
+					visuals::player_squash.after(player_controls),
+					visuals::player_trail,
+					visuals::player_dust.after(player_on_ground),
 				)
 					.after(input::InputSet)
 					.distributive_run_if(in_state(AppState::Game)),
@@ -34,7 +54,7 @@ impl Plugin for PlayerPlugin {
 	}
 }
 
-#[derive(Component, Reflect)]
+#[derive(Clone, Component, Reflect)]
 pub struct Player {
 	pub jump_vel: f32,
 	pub speed: f32,
@@ -44,10 +64,22 @@ pub struct Player {
 	pub in_air: bool,
 	pub on_wall: bool,
 	pub swapped: bool, // whether the left&right direction are swapped (useful for portals)
+	/// Material of the ground the player stands on, even if it doesn't restore their jump
+	pub surface: Option<Surface>,
+	/// Going down through one-way platforms, while ground pound is held
+	pub dropping: bool,
+	/// Normal of the slope the player stands on, pointing out of it
+	pub slope_normal: Option<Vec2>,
 }
 
 pub const PLAYER_SIZE: f32 = 0.5;
-const PLAYER_MAX_SPEED: f32 = 12.0;
+pub const PLAYER_MAX_SPEED: f32 = 12.0;
+/// Linear damping in the air, scaled by the friction of the ground
+const PLAYER_DAMPING: f32 = 2.0;
+/// Distance run between two footsteps
+const FOOTSTEP_STRIDE: f32 = 1.2;
+/// Speed under which the player stops on slopes when not running
+const SLOPE_MIN_SPEED: f32 = 1.0;
 
 #[derive(Component)]
 pub struct PlayerWalljumpSensor;
@@ -56,15 +88,16 @@ pub struct PlayerGroundSensor;
 
 pub struct SpawnPlayer {
 	pub pos: Vec2,
+	pub vel: Vec2,
 }
 fn player_spawn(
 	mut commands: Commands,
 	mut ev_spawn_player: EventReader<SpawnPlayer>,
-	q_camera: Query<Entity, With<Camera>>,
+	skins: Res<SkinSettings>,
+	leaderboard: Res<Leaderboard>,
+	nickname: Res<Nickname>,
 ) {
-	if let Some(SpawnPlayer { pos }) = ev_spawn_player.iter().last() {
-		let cam_entity = q_camera.single();
-
+	if let Some(SpawnPlayer { pos, vel }) = ev_spawn_player.iter().last() {
 		// Sensor for detecting walls (rectangle with the sides sticking out)
 		let walljump_sensor = commands
 			.spawn((
@@ -109,24 +142,22 @@ fn player_spawn(
 					in_air: true,
 					on_wall: false,
 					swapped: false,
+					surface: None,
+					dropping: false,
+					slope_normal: None,
 				},
-				Sprite {
-					color: Color::rgb(0.25, 0.25, 0.75),
-					custom_size: Some(Vec2::splat(PLAYER_SIZE)),
-					..default()
-				},
+				PlayerSkin(skins.skin_of(&leaderboard, &nickname.0).color),
 				SpatialBundle::from_transform(Transform::from_translation(pos.extend(1.0))),
-				DEFAULT_IMAGE_HANDLE.typed::<Image>(),
 				(
 					RigidBody::Dynamic,
-					Velocity::zero(),
+					Velocity::linear(*vel),
 					Collider::ball(PLAYER_SIZE / 2.0),
 					ExternalForce::default(),
 					ExternalImpulse::default(),
 					LockedAxes::ROTATION_LOCKED,
 					ColliderMassProperties::Density(10.0),
 					Damping {
-						linear_damping: 2.0,
+						linear_damping: PLAYER_DAMPING,
 						angular_damping: 0.0,
 					},
 					GravityScale(1.0),
@@ -135,40 +166,42 @@ fn player_spawn(
 						combine_rule: CoefficientCombineRule::Min,
 					},
 					CollidingEntities::default(),
-					Restitution::default(),
+					// bouncy surfaces bounce the player, whatever their restitution is
+					Restitution {
+						coefficient: 0.0,
+						combine_rule: CoefficientCombineRule::Max,
+					},
 					ActiveEvents::COLLISION_EVENTS,
 					Ccd::enabled(),
 				),
 				Exit(AppState::Game),
 			))
-			.add_child(cam_entity)
 			.add_child(walljump_sensor)
 			.add_child(ground_sensor);
 	}
 }
 
 pub fn player_controls(
-	asset_server: Res<AssetServer>,
-	audio: Res<Audio>,
+	mut ev_gameplay: EventWriter<GameplayEvent>,
 	action: Res<Input<Action>>,
-	time: Res<Time>,
+	analog: Res<AnalogActions>,
+	delta: StepDelta,
+	materials: Res<MaterialTable>,
 	mut q_player: Query<(
 		&mut Player,
 		&mut ExternalForce,
 		&mut GravityScale,
 		&mut Velocity,
 		&mut Friction,
+		&mut Damping,
 	)>,
 ) {
-	let Ok((mut player, mut ext_force, mut gravity, mut velocity, mut friction)) = q_player.get_single_mut() else {
+	let Ok((mut player, mut ext_force, mut gravity, mut velocity, mut friction, mut damping)) = q_player.get_single_mut() else {
 		return;
 	};
 
 	if action.just_pressed(Action::Jump) && player.remaining_jumps > 0 {
-		audio.play_with_settings(
-			asset_server.load("sounds/jump.ogg"),
-			PlaybackSettings::ONCE.with_volume(0.5),
-		);
+		ev_gameplay.send(GameplayEvent::Jumped);
 		velocity.linvel.y = velocity.linvel.y.max(player.jump_vel);
 		player.remaining_jumps -= 1;
 		player.jumping = true;
@@ -179,40 +212,57 @@ pub fn player_controls(
 		gravity.0 = 1.0;
 	}
 
+	player.dropping = action.pressed(Action::GroundPound);
 	if action.just_pressed(Action::GroundPound) {
-		audio.play_with_settings(
-			asset_server.load("sounds/ground_pound.ogg"),
-			PlaybackSettings::ONCE.with_volume(0.5),
-		);
+		ev_gameplay.send(GameplayEvent::GroundPounded);
 		velocity.linvel.y = -player.jump_vel * 2.0;
 		player.ground_pound = true;
 	}
 
-	ext_force.force = Vec2::ZERO;
-	if (action.pressed(Action::Left) && !player.swapped
-		|| action.pressed(Action::Right) && player.swapped)
-		&& velocity.linvel.x > -PLAYER_MAX_SPEED
-	{
-		if velocity.linvel.x > -PLAYER_MAX_SPEED {
-			velocity.linvel.x =
-				(velocity.linvel.x - player.speed * time.delta_seconds()).max(-PLAYER_MAX_SPEED);
-		}
+	// with analog movement, acceleration scales with how much the stick is tilted
+	let (mut left, mut right) = (
+		analog.strength(&action, Action::Left),
+		analog.strength(&action, Action::Right),
+	);
+	if player.swapped {
+		std::mem::swap(&mut left, &mut right);
 	}
-	if (action.pressed(Action::Right) && !player.swapped
-		|| action.pressed(Action::Left) && player.swapped)
-		&& velocity.linvel.x < PLAYER_MAX_SPEED
-	{
-		if velocity.linvel.x < PLAYER_MAX_SPEED {
-			velocity.linvel.x =
-				(velocity.linvel.x + player.speed * time.delta_seconds()).min(PLAYER_MAX_SPEED);
+
+	// slippery ground is slow to speed up on, and keeps the player sliding
+	let (accel, damping_scale) = match player.surface {
+		Some(surface) => {
+			let material = materials.get(surface);
+			(material.acceleration, material.friction)
 		}
+		None => (1.0, 1.0),
+	};
+	let accel = player.speed * accel * delta.seconds();
+	damping.linear_damping = PLAYER_DAMPING * damping_scale;
+
+	// on slopes the player runs along the ground, so that their speed carries up & down them
+	let run_dir = match player.slope_normal {
+		Some(normal) => -normal.perp(),
+		None => Vec2::X,
+	};
+	let speed = velocity.linvel.dot(run_dir);
+	let mut new_speed = speed;
+
+	ext_force.force = Vec2::ZERO;
+	if left > 0.0 && new_speed > -PLAYER_MAX_SPEED {
+		new_speed = (new_speed - accel * left).max(-PLAYER_MAX_SPEED);
+	}
+	if right > 0.0 && new_speed < PLAYER_MAX_SPEED {
+		new_speed = (new_speed + accel * right).min(PLAYER_MAX_SPEED);
 	}
+	velocity.linvel += run_dir * (new_speed - speed);
 
 	if action.just_released(Action::Left) || action.just_released(Action::Right) {
 		player.swapped = false;
 	}
 
-	if !action.pressed(Action::Left) && !action.pressed(Action::Right) {
+	// slopes keep the player's momentum until it runs out
+	let sliding = player.slope_normal.is_some() && velocity.linvel.length() > SLOPE_MIN_SPEED;
+	if left == 0.0 && right == 0.0 && !sliding {
 		// prevent sliding when the user is not moving sideways
 		friction.coefficient = 1.0;
 	} else {
@@ -220,23 +270,17 @@ pub fn player_controls(
 	}
 }
 
-fn player_render(mut q_player: Query<(&Player, &mut Sprite)>) {
-	let Ok((player, mut sprite)) = q_player.get_single_mut() else {
-		return;
-	};
-	if player.remaining_jumps != 0 {
-		sprite.color = Color::WHITE * 2.0;
-	} else {
-		sprite.color = Color::RED;
-	}
-}
-
 fn player_on_ground(
-	mut q_player: Query<&mut Player>,
+	mut ev_gameplay: EventWriter<GameplayEvent>,
+	rapier: Res<RapierContext>,
+	mut q_player: Query<(Entity, &mut Player)>,
 	q_sensor: Query<&CollidingEntities, With<PlayerGroundSensor>>,
 	q_wall: Query<Entity, With<RestoresJump>>,
+	q_surface: Query<&Surface>,
+	q_one_way: Query<(), With<OneWayPlatform>>,
+	q_slope: Query<(), With<SlopeCollider>>,
 ) {
-	let Ok(mut player) = q_player.get_single_mut() else {
+	let Ok((player_entity, mut player)) = q_player.get_single_mut() else {
 		return;
 	};
 
@@ -244,9 +288,42 @@ fn player_on_ground(
 		return;
 	};
 
-	player.in_air = !ground_sensor.iter().any(|e| q_wall.get(e).is_ok());
+	// the sensor also overlaps one-way platforms that the player goes through
+	let ground: Vec<Entity> = ground_sensor
+		.iter()
+		.filter(|e| !q_one_way.contains(*e) || one_way::stands_on(&rapier, player_entity, *e))
+		.collect();
+
+	// the lowest one when standing on several materials, so that it doesn't depend on their order
+	let surface = ground
+		.iter()
+		.filter_map(|e| q_surface.get(*e).ok())
+		.min()
+		.copied();
+	if player.surface.is_none() && surface.is_some() {
+		ev_gameplay.send(GameplayEvent::Landed);
+	}
+	player.surface = surface;
+
+	// the normal of the contact points out of the first collider
+	player.slope_normal = ground
+		.iter()
+		.filter(|e| q_slope.contains(**e))
+		.find_map(|e| {
+			let pair = rapier.contact_pair(player_entity, *e)?;
+			let manifold = pair.manifolds().find(|m| m.num_solver_contacts() > 0)?;
+			Some(match pair.collider1() == player_entity {
+				true => -manifold.normal(),
+				false => manifold.normal(),
+			})
+		})
+		// flat parts of merged slopes are regular ground
+		.filter(|normal| normal.y > 0.0 && normal.x.abs() > 1e-3);
+
+	player.in_air = !ground.iter().any(|e| q_wall.get(*e).is_ok());
 	if !player.in_air {
 		player.remaining_jumps = 1;
+		player.ground_pound = false;
 	}
 }
 
@@ -255,6 +332,7 @@ fn player_jumps(
 	mut q_player: Query<(&mut Player, &mut GravityScale, &Velocity)>,
 	mut q_walljump_sensor: Query<(Entity, &CollidingEntities), With<PlayerWalljumpSensor>>,
 	q_wall: Query<Option<&RestoresJump>>,
+	q_one_way: Query<(), With<OneWayPlatform>>,
 ) {
 	let Ok((mut player, mut gravity, velocity)) = q_player.get_single_mut() else {
 		return;
@@ -273,7 +351,8 @@ fn player_jumps(
 						_ => continue,
 					};
 					let restores_jump = q_wall.get(*wall_entity).unwrap();
-					if restores_jump.is_some() {
+					// one-way platforms are only ever stood on
+					if restores_jump.is_some() && !q_one_way.contains(*wall_entity) {
 						player.remaining_jumps = 1;
 					}
 				}
@@ -282,7 +361,9 @@ fn player_jumps(
 		}
 	}
 
-	player.on_wall = !walljump_colliding_entities.is_empty();
+	player.on_wall = walljump_colliding_entities
+		.iter()
+		.any(|e| !q_one_way.contains(e));
 
 	if player.jumping && velocity.linvel.y < 0.0 {
 		player.jumping = false;
@@ -290,6 +371,27 @@ fn player_jumps(
 	}
 }
 
+/// Footsteps follow the distance run on the ground, so they speed up with the player
+fn player_footsteps(
+	mut ev_gameplay: EventWriter<GameplayEvent>,
+	mut stride: Local<f32>,
+	delta: StepDelta,
+	q_player: Query<(&Player, &Velocity)>,
+) {
+	let Ok((player, velocity)) = q_player.get_single() else {
+		return;
+	};
+	if player.surface.is_none() {
+		*stride = 0.0;
+		return;
+	}
+	*stride += velocity.linvel.x.abs() * delta.seconds();
+	if *stride >= FOOTSTEP_STRIDE {
+		*stride -= FOOTSTEP_STRIDE;
+		ev_gameplay.send(GameplayEvent::Footstep);
+	}
+}
+
 fn player_restart(mut ev_restart: EventWriter<Restart>, q_player: Query<&Transform, With<Player>>) {
 	let Ok(transform) = q_player.get_single() else {
 		return;
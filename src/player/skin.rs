@@ -0,0 +1,103 @@
+///
+/// Player skins: each profile (nickname) picks the color they & their ghost are drawn with
+/// Some skins are unlocked by finishing levels
+///
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::leaderboard::Leaderboard;
+
+/// Red is left out, it shows that the player can't jump
+pub const SKINS: [Skin; 6] = [
+	Skin {
+		name: "Classic",
+		color: Color::rgb(1.0, 1.0, 1.0),
+		unlock: Unlock::Always,
+	},
+	Skin {
+		name: "Ocean",
+		color: Color::rgb(0.45, 0.6, 1.0),
+		unlock: Unlock::Always,
+	},
+	Skin {
+		name: "Mint",
+		color: Color::rgb(0.5, 1.0, 0.7),
+		unlock: Unlock::LevelsFinished(1),
+	},
+	Skin {
+		name: "Sun",
+		color: Color::rgb(1.0, 0.85, 0.35),
+		unlock: Unlock::LevelsFinished(3),
+	},
+	Skin {
+		name: "Violet",
+		color: Color::rgb(0.75, 0.5, 1.0),
+		unlock: Unlock::LevelsFinished(5),
+	},
+	Skin {
+		name: "Frost",
+		color: Color::rgb(0.5, 1.0, 1.0),
+		unlock: Unlock::LevelsFinished(10),
+	},
+];
+
+pub struct Skin {
+	pub name: &'static str,
+	/// Tint of the sprite, which is pale so that any color shows
+	pub color: Color,
+	pub unlock: Unlock,
+}
+
+#[derive(Clone, Copy)]
+pub enum Unlock {
+	Always,
+	/// Number of levels the profile has finished
+	LevelsFinished(usize),
+}
+
+impl Skin {
+	pub fn unlocked(&self, leaderboard: &Leaderboard, nickname: &str) -> bool {
+		match self.unlock {
+			Unlock::Always => true,
+			Unlock::LevelsFinished(count) => {
+				leaderboard
+					.0
+					.iter()
+					.filter(|scores| scores.contains_key(nickname))
+					.count() >= count
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for Unlock {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Unlock::Always => write!(f, "unlocked"),
+			Unlock::LevelsFinished(1) => write!(f, "finish a level"),
+			Unlock::LevelsFinished(count) => write!(f, "finish {count} levels"),
+		}
+	}
+}
+
+/// Skin chosen by each profile, by name
+#[derive(Clone, Default, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct SkinSettings {
+	pub skins: HashMap<String, String>,
+}
+
+impl SkinSettings {
+	/// Skin of a profile, the first one if they didn't choose or haven't unlocked theirs
+	pub fn skin_of(&self, leaderboard: &Leaderboard, nickname: &str) -> &'static Skin {
+		self.skins
+			.get(nickname)
+			.and_then(|name| SKINS.iter().find(|skin| skin.name == name))
+			.filter(|skin| skin.unlocked(leaderboard, nickname))
+			.unwrap_or(&SKINS[0])
+	}
+}
+
+/// Color the player is drawn with
+#[derive(Component)]
+pub struct PlayerSkin(pub Color);
@@ -0,0 +1,374 @@
+///
+/// What the player looks like: an animated sprite that squashes & stretches, and a trail
+/// All of it lives on children of the player, so it never touches their collider
+///
+use bevy::{prelude::*, utils::HashMap};
+use bevy_hanabi::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::{skin::PlayerSkin, Player, PLAYER_MAX_SPEED, PLAYER_SIZE};
+use crate::{
+	game::GameplayEvent,
+	level::material::{MaterialTable, Surface},
+	states::{AppState, Exit},
+	video::GraphicsQuality,
+};
+
+/// Size of a frame of the atlas, in pixels
+const FRAME_SIZE: f32 = 32.0;
+const ATLAS_COLUMNS: usize = 8;
+const ATLAS_ROWS: usize = 7;
+
+/// The art has some room around the body, so the sprite is larger than the collider
+pub const SPRITE_SIZE: f32 = PLAYER_SIZE * 1.5;
+
+/// How strongly the sprite springs back to its shape, and how fast it settles
+const SQUASH_STIFFNESS: f32 = 300.0;
+const SQUASH_DAMPING: f32 = 14.0;
+
+/// Deformation when jumping, and at most when landing
+const JUMP_STRETCH: f32 = 0.3;
+const MAX_LANDING_SQUASH: f32 = 0.4;
+
+/// Bounds of the deformation, so that the sprite never flips or vanishes
+const MAX_DEFORM: f32 = 0.5;
+
+/// Longest step of the spring, in seconds, it diverges past about 2 / sqrt(SQUASH_STIFFNESS)
+const MAX_SQUASH_DT: f32 = 1.0 / 30.0;
+
+/// Particles per second of the trail at full speed
+const TRAIL_RATE: f32 = 60.0;
+/// Below this fraction of the maximum speed, there is no trail
+const TRAIL_MIN_SPEED: f32 = 0.4;
+/// Replacing the spawner restarts it, so its rate only changes by steps
+const TRAIL_STEPS: f32 = 8.0;
+
+/// Particles of dust thrown up when landing, at full density
+const DUST_PARTICLES: f32 = 12.0;
+const DUST_LIFETIME: f32 = 0.4;
+
+#[derive(Resource)]
+pub struct PlayerAtlas(pub Handle<TextureAtlas>);
+
+pub fn load_player_atlas(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+	let atlas = TextureAtlas::from_grid(
+		asset_server.load("sprites/player.png"),
+		Vec2::splat(FRAME_SIZE),
+		ATLAS_COLUMNS,
+		ATLAS_ROWS,
+		None,
+		None,
+	);
+	commands.insert_resource(PlayerAtlas(atlases.add(atlas)));
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PlayerAnimation {
+	Idle,
+	Run,
+	Jump,
+	Fall,
+	WallSlide,
+	GroundPound,
+	Swing,
+}
+
+impl PlayerAnimation {
+	/// Row of the atlas, number of frames & frames per second
+	fn frames(self) -> (usize, usize, f32) {
+		match self {
+			PlayerAnimation::Idle => (0, 4, 4.0),
+			PlayerAnimation::Run => (1, 6, 12.0),
+			PlayerAnimation::Jump => (2, 2, 8.0),
+			PlayerAnimation::Fall => (3, 2, 8.0),
+			PlayerAnimation::WallSlide => (4, 2, 6.0),
+			PlayerAnimation::GroundPound => (5, 2, 12.0),
+			PlayerAnimation::Swing => (6, 4, 6.0),
+		}
+	}
+}
+
+/// Frame of the atlas shown for ghosts, which only have positions
+pub const GHOST_FRAME: usize = 0;
+
+/// The sprite of the player
+#[derive(Component)]
+pub struct PlayerSprite {
+	animation: PlayerAnimation,
+	/// Time spent in the current animation, in seconds
+	elapsed: f32,
+	/// Above 0 the sprite is stretched, below 0 it is squashed
+	deform: f32,
+	deform_vel: f32,
+	was_in_air: bool,
+	/// Vertical velocity of the player on the previous frame, which landing cancels
+	last_vel_y: f32,
+}
+
+/// Particles left behind the player, more of them the faster they go
+#[derive(Component)]
+pub struct PlayerTrail {
+	step: u32,
+}
+
+/// A puff of dust left where the player landed, despawned once its particles are gone
+#[derive(Component)]
+pub struct LandingDust(Timer);
+
+pub fn visuals_spawn(
+	mut commands: Commands,
+	atlas: Res<PlayerAtlas>,
+	mut effects: ResMut<Assets<EffectAsset>>,
+	quality: Res<GraphicsQuality>,
+	q_player: Query<Entity, Added<Player>>,
+) {
+	for player in &q_player {
+		let sprite = commands
+			.spawn((
+				PlayerSprite {
+					animation: PlayerAnimation::Fall,
+					elapsed: 0.0,
+					deform: 0.0,
+					deform_vel: 0.0,
+					was_in_air: true,
+					last_vel_y: 0.0,
+				},
+				SpriteSheetBundle {
+					sprite: TextureAtlasSprite {
+						custom_size: Some(Vec2::splat(SPRITE_SIZE)),
+						..default()
+					},
+					texture_atlas: atlas.0.clone(),
+					..default()
+				},
+			))
+			.id();
+
+		let mut gradient = Gradient::new();
+		gradient.add_key(0.0, Vec4::new(1.5, 1.5, 3.0, 0.6));
+		gradient.add_key(1.0, Vec4::new(0.5, 0.5, 1.0, 0.0));
+		let mut size_gradient = Gradient::new();
+		size_gradient.add_key(0.0, Vec2::splat(PLAYER_SIZE * 0.5));
+		size_gradient.add_key(1.0, Vec2::ZERO);
+		let effect = effects.add(
+			EffectAsset {
+				name: "PlayerTrail".into(),
+				capacity: quality.effect_capacity,
+				spawner: Spawner::rate(0.0.into()),
+				..Default::default()
+			}
+			.init(InitPositionCircleModifier {
+				center: Vec3::ZERO,
+				axis: Vec3::Z,
+				radius: PLAYER_SIZE / 4.0,
+				dimension: ShapeDimension::Volume,
+			})
+			.init(InitLifetimeModifier {
+				lifetime: 0.3f32.into(),
+			})
+			.render(SizeOverLifetimeModifier {
+				gradient: size_gradient,
+			})
+			.render(ColorOverLifetimeModifier { gradient }),
+		);
+		let trail = commands
+			.spawn((
+				PlayerTrail { step: 0 },
+				ParticleEffectBundle {
+					effect: ParticleEffect::new(effect).with_z_layer_2d(Some(0.9)),
+					..default()
+				},
+			))
+			.id();
+
+		commands.entity(player).push_children(&[sprite, trail]);
+	}
+}
+
+/// Picks the animation from what the player is doing, and tints the sprite with their skin
+pub fn player_animate(
+	time: Res<Time>,
+	q_player: Query<(&Player, &PlayerSkin, &Velocity, Option<&ImpulseJoint>)>,
+	mut q_sprite: Query<(&mut PlayerSprite, &mut TextureAtlasSprite)>,
+) {
+	let Ok((player, skin, velocity, joint)) = q_player.get_single() else {
+		return;
+	};
+	let Ok((mut state, mut sprite)) = q_sprite.get_single_mut() else {
+		return;
+	};
+
+	let vel = velocity.linvel;
+	let animation = match (joint.is_some(), player.in_air) {
+		(true, _) => PlayerAnimation::Swing,
+		(false, true) if player.ground_pound => PlayerAnimation::GroundPound,
+		(false, true) if player.on_wall && vel.y < 0.0 => PlayerAnimation::WallSlide,
+		(false, true) if vel.y > 0.0 => PlayerAnimation::Jump,
+		(false, true) => PlayerAnimation::Fall,
+		(false, false) if vel.x.abs() > 0.5 => PlayerAnimation::Run,
+		(false, false) => PlayerAnimation::Idle,
+	};
+	if animation != state.animation {
+		state.animation = animation;
+		state.elapsed = 0.0;
+	}
+	// the faster the player runs, the faster their legs go
+	state.elapsed += match animation {
+		PlayerAnimation::Run => time.delta_seconds() * (vel.x.abs() / PLAYER_MAX_SPEED).max(0.3),
+		_ => time.delta_seconds(),
+	};
+
+	let (row, len, fps) = animation.frames();
+	sprite.index = row * ATLAS_COLUMNS + (state.elapsed * fps) as usize % len;
+	if vel.x.abs() > 0.1 {
+		sprite.flip_x = vel.x < 0.0;
+	}
+
+	// red when the player can't jump, no skin is red so that it stays readable
+	sprite.color = match player.remaining_jumps {
+		0 => Color::RED,
+		_ => skin.0 * 2.0,
+	};
+}
+
+/// Stretches the sprite when jumping and squashes it when landing, then springs it back
+pub fn player_squash(
+	time: Res<Time>,
+	mut ev_gameplay: EventReader<GameplayEvent>,
+	q_player: Query<(&Player, &Velocity)>,
+	mut q_sprite: Query<(&mut PlayerSprite, &mut Transform)>,
+) {
+	let Ok((player, velocity)) = q_player.get_single() else {
+		return;
+	};
+	let Ok((mut state, mut transform)) = q_sprite.get_single_mut() else {
+		return;
+	};
+
+	if ev_gameplay.iter().any(|ev| *ev == GameplayEvent::Jumped) {
+		state.deform = JUMP_STRETCH;
+		state.deform_vel = 0.0;
+	}
+	if state.was_in_air && !player.in_air {
+		let impact = (-state.last_vel_y / PLAYER_MAX_SPEED).max(0.0);
+		state.deform = -(impact * 0.2).min(MAX_LANDING_SQUASH);
+		state.deform_vel = 0.0;
+	}
+	state.was_in_air = player.in_air;
+	state.last_vel_y = velocity.linvel.y;
+
+	let dt = time.delta_seconds().min(MAX_SQUASH_DT);
+	let accel = -SQUASH_STIFFNESS * state.deform - SQUASH_DAMPING * state.deform_vel;
+	state.deform_vel += accel * dt;
+	state.deform = (state.deform + state.deform_vel * dt).clamp(-MAX_DEFORM, MAX_DEFORM);
+
+	// the area stays the same, and the feet stay where they are
+	let scale = Vec2::new(1.0 / (1.0 + state.deform), 1.0 + state.deform);
+	transform.scale = scale.extend(1.0);
+	transform.translation.y = (scale.y - 1.0) * SPRITE_SIZE / 2.0;
+}
+
+pub fn player_trail(
+	quality: Res<GraphicsQuality>,
+	q_player: Query<&Velocity, With<Player>>,
+	mut q_trail: Query<(&mut PlayerTrail, &mut ParticleEffect)>,
+) {
+	let Ok(velocity) = q_player.get_single() else {
+		return;
+	};
+	let Ok((mut trail, mut effect)) = q_trail.get_single_mut() else {
+		return;
+	};
+
+	let speed = velocity.linvel.length() / PLAYER_MAX_SPEED;
+	let intensity = ((speed - TRAIL_MIN_SPEED) / (1.0 - TRAIL_MIN_SPEED)).clamp(0.0, 1.0);
+	let step = (intensity * TRAIL_STEPS).round() as u32;
+	if step != trail.step {
+		trail.step = step;
+		effect.set_spawner(quality.spawner(TRAIL_RATE * step as f32 / TRAIL_STEPS));
+	}
+}
+
+/// Throws up dust the color of the ground when the player lands
+pub fn player_dust(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut ev_gameplay: EventReader<GameplayEvent>,
+	materials: Res<MaterialTable>,
+	quality: Res<GraphicsQuality>,
+	mut effects: ResMut<Assets<EffectAsset>>,
+	mut dust_effects: Local<HashMap<Surface, Handle<EffectAsset>>>,
+	q_player: Query<(&Player, &Transform)>,
+	mut q_dust: Query<(Entity, &mut LandingDust)>,
+) {
+	for (entity, mut dust) in &mut q_dust {
+		if dust.0.tick(time.delta()).finished() {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+
+	if !ev_gameplay.iter().any(|ev| *ev == GameplayEvent::Landed) {
+		return;
+	}
+	let Ok((player, transform)) = q_player.get_single() else {
+		return;
+	};
+	let Some(surface) = player.surface else {
+		return;
+	};
+
+	let handle = dust_effects.entry(surface).or_insert_with(|| {
+		let color = materials.get(surface).particle_color();
+		let mut gradient = Gradient::new();
+		gradient.add_key(0.0, Vec4::from(color.as_rgba_f32()));
+		gradient.add_key(1.0, Vec4::from(color.with_a(0.0).as_rgba_f32()));
+		effects.add(
+			EffectAsset {
+				name: format!("LandingDust {}", materials.get(surface).name),
+				capacity: 64,
+				spawner: Spawner::once(DUST_PARTICLES.into(), true),
+				..Default::default()
+			}
+			.init(InitPositionCircleModifier {
+				center: Vec3::ZERO,
+				axis: Vec3::Z,
+				radius: PLAYER_SIZE / 4.0,
+				dimension: ShapeDimension::Volume,
+			})
+			// away from a point under the feet, so mostly sideways & up
+			.init(InitVelocityCircleModifier {
+				center: Vec3::new(0.0, -PLAYER_SIZE / 2.0, 0.0),
+				axis: Vec3::Z,
+				speed: Value::Uniform((1.0, 3.0)),
+			})
+			.init(InitLifetimeModifier {
+				lifetime: DUST_LIFETIME.into(),
+			})
+			.update(AccelModifier::constant(Vec3::new(0.0, -6.0, 0.0)))
+			.render(SizeOverLifetimeModifier {
+				gradient: Gradient::constant(Vec2::splat(PLAYER_SIZE * 0.3)),
+			})
+			.render(ColorOverLifetimeModifier { gradient }),
+		)
+	});
+
+	let mut effect = ParticleEffect::new(handle.clone()).with_z_layer_2d(Some(0.9));
+	effect.set_spawner(Spawner::once(
+		(DUST_PARTICLES * quality.particle_density).round().into(),
+		true,
+	));
+	let feet = transform.translation.truncate() - Vec2::new(0.0, PLAYER_SIZE / 2.0);
+	commands.spawn((
+		LandingDust(Timer::from_seconds(DUST_LIFETIME, TimerMode::Once)),
+		ParticleEffectBundle {
+			effect,
+			transform: Transform::from_translation(feet.extend(0.9)),
+			..default()
+		},
+		Exit(AppState::Game),
+	));
+}
@@ -1,16 +1,22 @@
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf};
 
 use anyhow::Result;
-use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE};
-use bevy_ecs_ldtk::{LdtkAsset, LevelSelection, LevelSet};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::{
-	game::StartTime,
+	game::{practice::PracticeMode, StartTime},
+	input::{Action, PLAYER_ACTIONS},
 	leaderboard::{Leaderboard, Nickname},
-	player::{Player, SpawnPlayer, PLAYER_SIZE},
+	player::{
+		skin::SkinSettings,
+		visuals::{PlayerAtlas, GHOST_FRAME, SPRITE_SIZE},
+		Player, SpawnPlayer,
+	},
 	states::{AppState, Exit},
+	tas::{Tas, TasMode},
 };
 
 pub struct ReplayPlugin;
@@ -21,13 +27,65 @@ impl Plugin for ReplayPlugin {
 			.add_system(recording_run.run_if(in_state(AppState::Game)).in_schedule(CoreSchedule::FixedUpdate))
 			.add_system(ghost_spawn.in_schedule(OnEnter(AppState::Game)))
 			.add_system(ghost_playback.run_if(in_state(AppState::Game)))
-			// .add_system(save_replay.in_schedule(OnEnter(AppState::Leaderboard)))
-			;
+			.add_system(save_replay.in_schedule(OnEnter(AppState::Leaderboard)));
 	}
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ReplayData(Vec<Vec2>);
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(from = "ReplayDataFormat")]
+pub struct ReplayData {
+	/// Position of the player at each tick
+	pub positions: Vec<Vec2>,
+	/// Actions held at each tick, empty for replays recorded without inputs
+	pub inputs: Vec<InputFrame>,
+}
+
+/// Formats of replay data found in saved files
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReplayDataFormat {
+	Current {
+		positions: Vec<Vec2>,
+		#[serde(default)]
+		inputs: Vec<InputFrame>,
+	},
+	/// Before inputs were recorded
+	PositionsOnly(Vec<Vec2>),
+}
+
+impl From<ReplayDataFormat> for ReplayData {
+	fn from(format: ReplayDataFormat) -> Self {
+		match format {
+			ReplayDataFormat::Current { positions, inputs } => Self { positions, inputs },
+			ReplayDataFormat::PositionsOnly(positions) => Self {
+				positions,
+				inputs: vec![],
+			},
+		}
+	}
+}
+
+impl ReplayData {
+	/// Number of ticks recorded
+	pub fn ticks(&self) -> usize {
+		self.positions.len()
+	}
+}
+
+/// Actions held during a tick
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputFrame(pub BTreeSet<Action>);
+
+impl InputFrame {
+	pub fn capture(actions: &Input<Action>) -> Self {
+		Self(
+			PLAYER_ACTIONS
+				.into_iter()
+				.filter(|a| actions.pressed(*a))
+				.collect(),
+		)
+	}
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Replay {
@@ -35,6 +93,9 @@ pub struct Replay {
 	timestamp: OffsetDateTime,
 	level: usize, // todo: use uuid
 	data: ReplayData,
+	/// Tool-assisted runs never enter the leaderboard
+	#[serde(default)]
+	tas: bool,
 }
 
 #[derive(Resource)]
@@ -42,19 +103,39 @@ pub struct ReplayRecording(pub ReplayData);
 
 fn recording_run(
 	q_player: Query<&Transform, With<Player>>,
+	actions: Res<Input<Action>>,
 	mut ev_player_spawned: EventReader<SpawnPlayer>,
 	mut replay: ResMut<ReplayRecording>,
 ) {
 	if ev_player_spawned.iter().count() > 0 {
-		*replay = ReplayRecording(ReplayData(vec![]));
+		*replay = ReplayRecording(default());
 	}
 	for tr in q_player.iter() {
-		replay.0 .0.push(tr.translation.truncate());
+		replay.0.positions.push(tr.translation.truncate());
+		replay.0.inputs.push(InputFrame::capture(&actions));
 	}
 }
 
 pub fn recording_start(mut commands: Commands) {
-	commands.insert_resource(ReplayRecording(ReplayData(vec![])));
+	commands.insert_resource(ReplayRecording(default()));
+}
+
+/// How the ghost of the best run is shown
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct GhostSettings {
+	pub enabled: bool,
+	/// Opacity of the ghost when it is away from the player
+	pub opacity: f32,
+}
+
+impl Default for GhostSettings {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			opacity: 0.2,
+		}
+	}
 }
 
 pub fn ghost_spawn(
@@ -62,44 +143,64 @@ pub fn ghost_spawn(
 	leaderboard: Res<Leaderboard>,
 	level_selection: ResMut<LevelSelection>,
 	nickname: Res<Nickname>,
+	settings: Res<GhostSettings>,
+	skins: Res<SkinSettings>,
+	atlas: Res<PlayerAtlas>,
 ) {
+	if !settings.enabled {
+		return;
+	}
 	let LevelSelection::Index(level) = level_selection.clone() else {
 		panic!("expected level index");
 	};
 
 	if let Some((_, replay)) = leaderboard.0[level].get(&nickname.0) {
-		let Some(first_position) = replay.0.first() else {
+		let Some(first_position) = replay.positions.first() else {
 			return;
 		};
+		// ghosts look like their owner, faded by the playback
+		let skin = skins.skin_of(&leaderboard, &nickname.0);
 		commands.spawn((
 			Ghost(replay.clone()),
-			Sprite {
-				color: Color::WHITE,
-				custom_size: Some(Vec2::splat(PLAYER_SIZE)),
+			SpriteSheetBundle {
+				sprite: TextureAtlasSprite {
+					index: GHOST_FRAME,
+					color: skin.color.with_a(0.0),
+					custom_size: Some(Vec2::splat(SPRITE_SIZE)),
+					..default()
+				},
+				texture_atlas: atlas.0.clone(),
+				transform: Transform::from_translation(first_position.extend(0.5)),
 				..default()
 			},
-			SpatialBundle::from_transform(Transform::from_translation(first_position.extend(0.5))),
-			DEFAULT_IMAGE_HANDLE.typed::<Image>(),
 			Exit(AppState::Game),
 		));
 	}
 }
 
 #[derive(Component)]
-pub struct Ghost(ReplayData);
+pub struct Ghost(pub ReplayData);
+
+impl Ghost {
+	/// Current tick of the ghost's replay, with the fraction elapsed towards the next one
+	pub fn tick(start_time: &StartTime) -> f32 {
+		start_time.0.elapsed().as_secs_f32() * 60.0
+	}
+}
 
 pub fn ghost_playback(
-	mut q_ghost: Query<(&Ghost, &mut Transform, &mut Sprite)>,
+	mut q_ghost: Query<(&Ghost, &mut Transform, &mut TextureAtlasSprite)>,
 	start_time: Res<StartTime>,
 	q_player: Query<&Transform, (With<Player>, Without<Ghost>)>,
+	settings: Res<GhostSettings>,
 ) {
 	if let Ok((ghost, mut tr, mut sprite)) = q_ghost.get_single_mut() {
-		let since_start = start_time.0.elapsed().as_secs_f32() * 60.0;
+		let since_start = Ghost::tick(&start_time);
 
-		let Some(pos_prev) = ghost.0 .0.get(since_start as usize) else {
+		let Some(pos_prev) = ghost.0.positions.get(since_start as usize) else {
 			return;
 		};
-		let Some(pos_next) = ghost.0 .0.get(since_start as usize + 1) else {
+		let Some(pos_next) = ghost.0.positions.get(since_start as usize + 1) else {
 			return;
 		};
 
@@ -107,53 +208,82 @@ pub fn ghost_playback(
 
 		let pos = *pos_prev * (1.0 - t) + *pos_next * t;
 		tr.translation = pos.extend(tr.translation.z);
+		if (pos_next.x - pos_prev.x).abs() > f32::EPSILON {
+			sprite.flip_x = pos_next.x < pos_prev.x;
+		}
 
 		if let Ok(player_tr) = q_player.get_single() {
 			const MIN_DIST: f32 = 2.0;
 			const MAX_DIST: f32 = 10.0;
 			let alpha = ((pos.distance_squared(player_tr.translation.truncate()) - MIN_DIST)
 				/ (MAX_DIST - MIN_DIST))
-				.clamp(0.0, 1.0) * 0.2;
+				.clamp(0.0, 1.0)
+				* settings.opacity;
 			sprite.color = sprite.color.with_a(alpha);
 		}
 	}
 }
 
-// pub fn save_replay(
-// 	mut level_selection: ResMut<LevelSelection>,
-// 	q_ldtk_world: Query<(Entity, &Handle<LdtkAsset>), With<LevelSet>>,
-// 	ldtk_asset: Res<Assets<LdtkAsset>>,
-// 	replay: Res<RecordingReplay>,
-// ) {
-// 	let LevelSelection::Index(level) = level_selection.clone() else {
-// 		panic!("expected level index");
-// 	};
-
-// 	if let Err(e) = try_save(&Replay {
-// 		timestamp: OffsetDateTime::now_utc(),
-// 		level,
-// 		data: replay.0.clone(),
-// 	}) {
-// 		warn!("failed to save replay: {e}");
-// 	}
-// }
-
-// fn try_save(replay: &Replay) -> Result<()> {
-// 	let s = serde_json::to_string_pretty(replay)?;
-// 	let path = replay_path().join(format!(
-// 		"{}-{}-{}",
-// 		replay.nickname,
-// 		replay.level,
-// 		replay.timestamp.unix_timestamp()
-// 	));
-// 	std::fs::create_dir_all(path.parent().unwrap())?;
-// 	std::fs::write(path, s)?;
-// 	Ok(())
-// }
-
-// fn replay_path() -> PathBuf {
-// 	directories::ProjectDirs::from("", "Azorlogh", "Speeed")
-// 		.unwrap()
-// 		.data_dir()
-// 		.to_owned()
-// }
+fn save_replay(
+	level_selection: Res<LevelSelection>,
+	nickname: Res<Nickname>,
+	recording: Res<ReplayRecording>,
+	practice_mode: Res<PracticeMode>,
+	tas_mode: Res<TasMode>,
+	tas: Res<Tas>,
+) {
+	let LevelSelection::Index(level) = level_selection.clone() else {
+		panic!("expected level index");
+	};
+
+	// Practice runs skip parts of the level, they aren't worth keeping
+	if practice_mode.0 {
+		return;
+	}
+
+	// The timeline is what the run was made of, even for ticks that didn't press anything
+	let mut data = recording.0.clone();
+	if tas_mode.0 {
+		data.inputs = tas.timeline.iter().take(data.ticks()).cloned().collect();
+	}
+
+	if let Err(e) = try_save(&Replay {
+		nickname: nickname.0.clone(),
+		timestamp: OffsetDateTime::now_utc(),
+		level,
+		data,
+		tas: tas_mode.0,
+	}) {
+		warn!("failed to save replay: {e}");
+	}
+}
+
+fn try_save(replay: &Replay) -> Result<()> {
+	let s = serde_json::to_string_pretty(replay)?;
+	// nicknames are typed by players, keep them from reaching outside of the directory
+	let mut nickname: String = replay
+		.nickname
+		.chars()
+		.filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+		.collect();
+	if nickname.is_empty() {
+		nickname = "player".into();
+	}
+	let path = replay_path().join(format!(
+		"{}-{}-{}{}.json",
+		nickname,
+		replay.level,
+		replay.timestamp.unix_timestamp(),
+		if replay.tas { "-tas" } else { "" },
+	));
+	std::fs::create_dir_all(path.parent().unwrap())?;
+	std::fs::write(path, s)?;
+	Ok(())
+}
+
+fn replay_path() -> PathBuf {
+	directories::ProjectDirs::from("", "Azorlogh", "Speeed")
+		.unwrap()
+		.data_dir()
+		.join("replays")
+}
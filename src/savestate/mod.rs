@@ -0,0 +1,263 @@
+///
+/// Savestates: snapshot the whole run state and restore it instantly
+/// Only available outside of ranked runs
+///
+use std::time::{Duration, Instant};
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::LevelSelection;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+	game::{
+		practice::{PracticeMode, Segment},
+		StartTime,
+	},
+	input::{self, Action},
+	level::rope::player_rope_joint,
+	player::Player,
+	replay::{ReplayData, ReplayRecording},
+	states::AppState,
+	tas::TasMode,
+};
+
+pub struct SavestatePlugin;
+
+impl Plugin for SavestatePlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(Savestates::default())
+			.add_event::<SaveState>()
+			.add_event::<LoadState>()
+			.add_system(savestate_shortcuts.after(input::InputSet))
+			.add_systems(
+				(save_state, load_state.after(save_state))
+					.after(savestate_shortcuts)
+					.distributive_run_if(in_state(AppState::Game))
+					.distributive_run_if(savestates_allowed),
+			);
+	}
+}
+
+/// Savestates are never allowed in ranked runs
+pub fn savestates_allowed(practice_mode: Res<PracticeMode>, tas_mode: Res<TasMode>) -> bool {
+	practice_mode.0 || tas_mode.0
+}
+
+/// Identifies a level object across level respawns
+/// Objects with this component have their transform (and velocity) captured in savestates
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SavestateKey([u32; 2]);
+
+impl SavestateKey {
+	/// Objects are identified by where they were spawned
+	pub fn from_spawn_pos(pos: Vec2) -> Self {
+		Self([pos.x.to_bits(), pos.y.to_bits()])
+	}
+}
+
+#[derive(Clone)]
+pub struct BodyState {
+	transform: Transform,
+	velocity: Option<Velocity>,
+}
+
+/// Physics state of the player that their systems change along the run
+#[derive(Clone)]
+pub struct PlayerBody {
+	transform: Transform,
+	velocity: Velocity,
+	gravity: GravityScale,
+	friction: Friction,
+	damping: Damping,
+	force: ExternalForce,
+	impulse: ExternalImpulse,
+}
+
+/// A snapshot of the run
+#[derive(Clone)]
+pub struct Savestate {
+	level: usize,
+	player: Player,
+	player_body: PlayerBody,
+	rope: Option<SavestateKey>,
+	objects: HashMap<SavestateKey, BodyState>,
+	replay: ReplayData,
+	segment_elapsed: Duration,
+}
+
+impl Savestate {
+	/// Number of fixed timesteps elapsed since the start of the run
+	pub fn elapsed_ticks(&self) -> usize {
+		self.replay.ticks()
+	}
+}
+
+/// Stored savestates, by slot
+#[derive(Default, Resource)]
+pub struct Savestates(pub HashMap<usize, Savestate>);
+
+/// Event to snapshot the run into a slot
+pub struct SaveState {
+	pub slot: usize,
+}
+
+/// Event to restore the run from a slot
+pub struct LoadState {
+	pub slot: usize,
+}
+
+fn savestate_shortcuts(
+	actions: Res<Input<Action>>,
+	mut ev_save_state: EventWriter<SaveState>,
+	mut ev_load_state: EventWriter<LoadState>,
+) {
+	if actions.just_pressed(Action::SaveState) {
+		ev_save_state.send(SaveState { slot: 0 });
+	}
+	if actions.just_pressed(Action::LoadState) {
+		ev_load_state.send(LoadState { slot: 0 });
+	}
+}
+
+fn save_state(
+	mut ev_save_state: EventReader<SaveState>,
+	mut savestates: ResMut<Savestates>,
+	q_player: Query<(
+		&Player,
+		(
+			&Transform,
+			&Velocity,
+			&GravityScale,
+			&Friction,
+			&Damping,
+			&ExternalForce,
+			&ExternalImpulse,
+		),
+		Option<&ImpulseJoint>,
+	)>,
+	q_objects: Query<(&SavestateKey, &Transform, Option<&Velocity>), Without<Player>>,
+	level_selection: Res<LevelSelection>,
+	recording: Res<ReplayRecording>,
+	segment: Res<Segment>,
+) {
+	let LevelSelection::Index(level) = level_selection.clone() else {
+		panic!("expected level index");
+	};
+	let Ok((player, body, joint)) = q_player.get_single() else {
+		return;
+	};
+	let (transform, velocity, gravity, friction, damping, force, impulse) = body;
+
+	for SaveState { slot } in ev_save_state.iter() {
+		let savestate = Savestate {
+			level,
+			player: player.clone(),
+			player_body: PlayerBody {
+				transform: *transform,
+				velocity: *velocity,
+				gravity: *gravity,
+				friction: *friction,
+				damping: *damping,
+				force: *force,
+				impulse: *impulse,
+			},
+			rope: joint.and_then(|j| q_objects.get(j.parent).ok().map(|(k, _, _)| *k)),
+			objects: q_objects
+				.iter()
+				.map(|(key, transform, velocity)| {
+					(
+						*key,
+						BodyState {
+							transform: *transform,
+							velocity: velocity.copied(),
+						},
+					)
+				})
+				.collect(),
+			replay: recording.0.clone(),
+			segment_elapsed: segment.start.elapsed(),
+		};
+		savestates.0.insert(*slot, savestate);
+	}
+}
+
+fn load_state(
+	mut commands: Commands,
+	mut ev_load_state: EventReader<LoadState>,
+	savestates: Res<Savestates>,
+	mut q_player: Query<(
+		Entity,
+		&mut Player,
+		(
+			&mut Transform,
+			&mut Velocity,
+			&mut GravityScale,
+			&mut Friction,
+			&mut Damping,
+			&mut ExternalForce,
+			&mut ExternalImpulse,
+		),
+	)>,
+	mut q_objects: Query<
+		(Entity, &SavestateKey, &mut Transform, Option<&mut Velocity>),
+		Without<Player>,
+	>,
+	level_selection: Res<LevelSelection>,
+	fixed_time: Res<FixedTime>,
+	mut start_time: ResMut<StartTime>,
+	mut recording: ResMut<ReplayRecording>,
+	mut segment: ResMut<Segment>,
+) {
+	let LevelSelection::Index(level) = level_selection.clone() else {
+		panic!("expected level index");
+	};
+	let Ok((player_entity, mut player, body)) = q_player.get_single_mut() else {
+		return;
+	};
+	let (
+		mut transform,
+		mut velocity,
+		mut gravity,
+		mut friction,
+		mut damping,
+		mut force,
+		mut impulse,
+	) = body;
+
+	for LoadState { slot } in ev_load_state.iter() {
+		let Some(savestate) = savestates.0.get(slot).filter(|s| s.level == level) else {
+			continue;
+		};
+
+		*player = savestate.player.clone();
+		let body = &savestate.player_body;
+		*transform = body.transform;
+		*velocity = body.velocity;
+		*gravity = body.gravity;
+		*friction = body.friction;
+		*damping = body.damping;
+		*force = body.force;
+		*impulse = body.impulse;
+
+		commands.entity(player_entity).remove::<ImpulseJoint>();
+		for (entity, key, mut transform, velocity) in &mut q_objects {
+			if let Some(state) = savestate.objects.get(key) {
+				*transform = state.transform;
+				if let (Some(mut velocity), Some(saved)) = (velocity, state.velocity) {
+					*velocity = saved;
+				}
+			}
+			if savestate.rope == Some(*key) {
+				commands
+					.entity(player_entity)
+					.insert(ImpulseJoint::new(entity, player_rope_joint()));
+			}
+		}
+
+		let now = Instant::now();
+		let elapsed = fixed_time.period * savestate.elapsed_ticks() as u32;
+		start_time.0 = now.checked_sub(elapsed).unwrap_or(now);
+		segment.start = now.checked_sub(savestate.segment_elapsed).unwrap_or(now);
+		recording.0 = savestate.replay.clone();
+	}
+}
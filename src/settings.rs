@@ -2,9 +2,24 @@ use std::{fs::read_to_string, path::PathBuf};
 
 use anyhow::Result;
 use bevy::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{input::InputMapping, leaderboard::Nickname};
+use crate::{
+	audio::AudioSettings,
+	game::{intro::IntroSettings, juice::MotionSettings},
+	input::InputMapping,
+	input_display::InputDisplay,
+	leaderboard::Nickname,
+	player::skin::SkinSettings,
+	replay::GhostSettings,
+	video::{GraphicsQuality, VideoSettings},
+};
+
+/// Version of the settings file written by this build
+const VERSION: usize = 1;
+
+/// `MIGRATIONS[v]` upgrades a settings file from version `v` to the next one
+const MIGRATIONS: [fn(&mut toml::Table); VERSION] = [migrate_v0];
 
 pub struct SettingsPlugin;
 
@@ -12,7 +27,15 @@ impl Plugin for SettingsPlugin {
 	fn build(&self, app: &mut App) {
 		let settings = load_settings();
 		app.insert_resource(settings.nickname)
-			.insert_resource(settings.input_mapping)
+			.insert_resource(settings.input)
+			.insert_resource(settings.input_display)
+			.insert_resource(settings.audio)
+			.insert_resource(settings.ghosts)
+			.insert_resource(settings.skins)
+			.insert_resource(settings.intro)
+			.insert_resource(settings.motion)
+			.insert_resource(settings.video)
+			.insert_resource(settings.graphics)
 			.add_event::<SaveSettings>()
 			.add_system(settings_save);
 	}
@@ -25,25 +48,128 @@ fn settings_path() -> PathBuf {
 		.join("settings.toml")
 }
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Serialize)]
 pub struct Settings {
+	version: usize,
 	nickname: Nickname,
-	input_mapping: InputMapping,
+	input: InputMapping,
+	input_display: InputDisplay,
+	audio: AudioSettings,
+	ghosts: GhostSettings,
+	skins: SkinSettings,
+	intro: IntroSettings,
+	motion: MotionSettings,
+	video: VideoSettings,
+	graphics: GraphicsQuality,
 }
 
 fn load_settings() -> Settings {
-	match try_load_settings() {
-		Ok(s) => s,
+	let mut table = match try_read_settings() {
+		Ok(table) => table,
 		Err(e) => {
 			warn!("failed to load settings, using defaults: {e}");
 			default()
 		}
+	};
+	upgrade(&mut table);
+
+	Settings {
+		version: VERSION,
+		nickname: load_section(&mut table, "nickname"),
+		input: load_section(&mut table, "input"),
+		input_display: load_section(&mut table, "input_display"),
+		audio: load_section(&mut table, "audio"),
+		ghosts: load_section(&mut table, "ghosts"),
+		skins: load_section(&mut table, "skins"),
+		intro: load_section(&mut table, "intro"),
+		motion: load_section(&mut table, "motion"),
+		video: load_section(&mut table, "video"),
+		graphics: load_section(&mut table, "graphics"),
 	}
 }
 
-fn try_load_settings() -> Result<Settings> {
-	let s = read_to_string(settings_path())?;
-	Ok(serde_json::from_str(&s)?)
+fn try_read_settings() -> Result<toml::Table> {
+	parse_settings(&read_to_string(settings_path())?)
+}
+
+fn parse_settings(s: &str) -> Result<toml::Table> {
+	match s.parse::<toml::Table>() {
+		Ok(table) => Ok(table),
+		Err(e) => match serde_json::from_str(s) {
+			Ok(serde_json::Value::Object(object)) => Ok(json_to_toml(object)),
+			_ => Err(e.into()),
+		},
+	}
+}
+
+/// Brings settings written by an older version up to date
+fn upgrade(table: &mut toml::Table) {
+	// files without a version were written as JSON, before versions existed
+	let version = match table.remove("version") {
+		Some(toml::Value::Integer(v)) => v as usize,
+		_ => 0,
+	};
+	if version > VERSION {
+		warn!("settings were written by a newer version ({version}), some may be lost");
+	}
+	for migrate in MIGRATIONS.iter().skip(version) {
+		migrate(table);
+	}
+}
+
+/// Loads a section of the settings, leaving out its invalid fields
+fn load_section<T: DeserializeOwned + Default>(table: &mut toml::Table, key: &str) -> T {
+	let Some(value) = table.remove(key) else {
+		return default();
+	};
+	match value.clone().try_into() {
+		Ok(section) => return section,
+		Err(e) => warn!("invalid settings in `{key}`: {e}"),
+	}
+
+	// keep the fields that are valid on their own
+	let toml::Value::Table(fields) = value else {
+		return default();
+	};
+	let mut valid = toml::Table::new();
+	for (field, value) in fields {
+		valid.insert(field.clone(), value);
+		if toml::Value::Table(valid.clone()).try_into::<T>().is_err() {
+			warn!("ignoring invalid setting `{key}.{field}`");
+			valid.remove(&field);
+		}
+	}
+	toml::Value::Table(valid).try_into().unwrap_or_default()
+}
+
+/// Version 0 was JSON, with the input mapping stored as `input_mapping`
+fn migrate_v0(table: &mut toml::Table) {
+	if let Some(input) = table.remove("input_mapping") {
+		table.insert("input".to_owned(), input);
+	}
+}
+
+/// TOML has no null, absent values are left out instead
+fn json_to_toml(object: serde_json::Map<String, serde_json::Value>) -> toml::Table {
+	fn convert(value: serde_json::Value) -> Option<toml::Value> {
+		Some(match value {
+			serde_json::Value::Null => return None,
+			serde_json::Value::Bool(b) => toml::Value::Boolean(b),
+			serde_json::Value::Number(n) => match n.as_i64() {
+				Some(i) => toml::Value::Integer(i),
+				None => toml::Value::Float(n.as_f64()?),
+			},
+			serde_json::Value::String(s) => toml::Value::String(s),
+			serde_json::Value::Array(values) => {
+				toml::Value::Array(values.into_iter().filter_map(convert).collect())
+			}
+			serde_json::Value::Object(object) => toml::Value::Table(json_to_toml(object)),
+		})
+	}
+	object
+		.into_iter()
+		.filter_map(|(key, value)| Some((key, convert(value)?)))
+		.collect()
 }
 
 pub struct SaveSettings;
@@ -51,12 +177,29 @@ pub struct SaveSettings;
 fn settings_save(
 	mut ev_save_settings: EventReader<SaveSettings>,
 	nickname: Res<Nickname>,
-	input_mapping: Res<InputMapping>,
+	input: Res<InputMapping>,
+	input_display: Res<InputDisplay>,
+	audio: Res<AudioSettings>,
+	ghosts: Res<GhostSettings>,
+	skins: Res<SkinSettings>,
+	intro: Res<IntroSettings>,
+	motion: Res<MotionSettings>,
+	video: Res<VideoSettings>,
+	graphics: Res<GraphicsQuality>,
 ) {
 	if ev_save_settings.iter().count() > 0 {
 		if let Err(e) = try_save(Settings {
+			version: VERSION,
 			nickname: nickname.clone(),
-			input_mapping: input_mapping.clone(),
+			input: input.clone(),
+			input_display: input_display.clone(),
+			audio: audio.clone(),
+			ghosts: ghosts.clone(),
+			skins: skins.clone(),
+			intro: intro.clone(),
+			motion: motion.clone(),
+			video: video.clone(),
+			graphics: graphics.clone(),
 		}) {
 			error!("failed to save settings: {e:?}")
 		}
@@ -64,9 +207,112 @@ fn settings_save(
 }
 
 fn try_save(settings: Settings) -> Result<()> {
-	let s = serde_json::to_string_pretty(&settings)?;
+	let s = toml::to_string_pretty(&settings)?;
 	let path = settings_path();
 	std::fs::create_dir_all(path.parent().unwrap())?;
 	std::fs::write(path, s)?;
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::input::{Action, ButtonOrAxis};
+
+	#[test]
+	fn v0_json() {
+		let mut table = parse_settings(
+			r#"{
+				"nickname": "Speedy",
+				"input_mapping": {
+					"Jump": {
+						"key": "W",
+						"button_or_axis": { "Button": "East" }
+					},
+					"Left": {
+						"key": "A",
+						"button_or_axis": { "Axis": ["LeftStickY", true] }
+					},
+					"Right": {
+						"key": "D",
+						"button_or_axis": { "Axis": ["LeftStickX", false] }
+					},
+					"GroundPound": {
+						"key": "S",
+						"button_or_axis": { "Button": "West" }
+					},
+					"Restart": {
+						"key": "R",
+						"button_or_axis": { "Button": "North" }
+					}
+				}
+			}"#,
+		)
+		.unwrap();
+		upgrade(&mut table);
+
+		assert!(!table.contains_key("input_mapping"));
+		let nickname: Nickname = load_section(&mut table, "nickname");
+		assert_eq!(nickname.0, "Speedy");
+		let input: InputMapping = load_section(&mut table, "input");
+		let jump = &input.actions[&Action::Jump];
+		assert_eq!(jump.keys, [KeyCode::W]);
+		assert_eq!(
+			jump.buttons,
+			[ButtonOrAxis::Button(GamepadButtonType::East)]
+		);
+		let left = &input.actions[&Action::Left];
+		assert_eq!(left.keys, [KeyCode::A]);
+		assert_eq!(
+			left.buttons,
+			[ButtonOrAxis::Axis(GamepadAxisType::LeftStickY, true)]
+		);
+		// actions added since then get their default bindings
+		assert_eq!(
+			input.actions[&Action::SaveState].keys,
+			InputMapping::default().actions[&Action::SaveState].keys
+		);
+	}
+
+	#[test]
+	fn partially_invalid_section() {
+		let mut table = parse_settings(
+			r#"
+				version = 1
+				[audio]
+				master = 0.8
+				music = "loud"
+				sfx = 0.1
+			"#,
+		)
+		.unwrap();
+		upgrade(&mut table);
+
+		let audio: AudioSettings = load_section(&mut table, "audio");
+		assert_eq!(audio.master, 0.8);
+		assert_eq!(audio.music, AudioSettings::default().music);
+		assert_eq!(audio.sfx, 0.1);
+	}
+
+	#[test]
+	fn newer_version() {
+		let mut table = parse_settings(
+			r#"
+				version = 1000
+				[input_mapping]
+				future_field = 1
+				[audio]
+				master = 0.5
+				future_bus = 0.7
+			"#,
+		)
+		.unwrap();
+		upgrade(&mut table);
+
+		// not mistaken for the version 0 input mapping
+		assert!(table.contains_key("input_mapping"));
+		let audio: AudioSettings = load_section(&mut table, "audio");
+		assert_eq!(audio.master, 0.5);
+		assert_eq!(audio.music, AudioSettings::default().music);
+	}
+}
@@ -0,0 +1,213 @@
+///
+/// Tool-assisted runs: the simulation is paused and advanced one tick at a time,
+/// using inputs edited on a timeline instead of the keyboard/gamepad.
+///
+use std::time::Instant;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+	game::{Restart, StartTime},
+	input::{Action, AnalogActions, InputMapping, InputSet, PLAYER_ACTIONS},
+	player::Player,
+	replay::{InputFrame, ReplayRecording},
+	states::AppState,
+};
+
+mod ui;
+
+/// Actions that can be edited on the timeline
+pub const TAS_ACTIONS: [Action; 4] = PLAYER_ACTIONS;
+
+pub struct TasPlugin;
+
+impl Plugin for TasPlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(TasMode(false))
+			.insert_resource(Tas::default())
+			.add_system(tas_step.in_base_set(CoreSet::PreUpdate).run_if(tas_running))
+			.add_system(tas_input.in_set(InputSet).run_if(tas_running))
+			.add_systems((tas_shortcuts, ui::tas_ui).distributive_run_if(tas_running))
+			.add_system(tas_exit.in_schedule(OnExit(AppState::Game)))
+			.add_system(tas_clear.in_schedule(OnEnter(AppState::Menu)));
+	}
+}
+
+/// Whether runs are tool-assisted
+#[derive(Resource)]
+pub struct TasMode(pub bool);
+
+/// True while playing a level in TAS mode
+pub fn tas_running(tas_mode: Res<TasMode>, state: Res<State<AppState>>) -> bool {
+	tas_mode.0 && state.0 == AppState::Game
+}
+
+#[derive(Default, Resource)]
+pub struct Tas {
+	/// Inputs for each tick of the run
+	pub timeline: Vec<InputFrame>,
+	/// Whether ticks are simulated continuously
+	pub playing: bool,
+	/// Keep simulating until this tick is reached (used to replay edited inputs)
+	pub play_until: Option<usize>,
+	/// Simulate a single tick on the next frame
+	pub advance: bool,
+	/// The tick being simulated during this frame, if any
+	pub step: Option<usize>,
+	/// Timestep mode to restore when leaving the TAS
+	timestep_mode: Option<TimestepMode>,
+}
+
+impl Tas {
+	/// Restart the level and simulate back up to the given tick
+	pub fn replay_until(&mut self, tick: usize, ev_restart: &mut EventWriter<Restart>) {
+		self.playing = false;
+		self.play_until = Some(tick);
+		ev_restart.send(Restart);
+	}
+
+	pub fn toggle(&mut self, tick: usize, action: Action) {
+		if self.timeline.len() <= tick {
+			self.timeline.resize(tick + 1, default());
+		}
+		let frame = &mut self.timeline[tick].0;
+		if !frame.remove(&action) {
+			frame.insert(action);
+		}
+	}
+}
+
+/// Duration of the current simulation step
+/// While in TAS mode, it is exactly one tick when stepping and zero otherwise
+#[derive(SystemParam)]
+pub struct StepDelta<'w> {
+	time: Res<'w, Time>,
+	fixed_time: Res<'w, FixedTime>,
+	tas: Res<'w, Tas>,
+}
+
+impl<'w> StepDelta<'w> {
+	pub fn seconds(&self) -> f32 {
+		match self.tas.step {
+			Some(_) => self.fixed_time.period.as_secs_f32(),
+			None => self.time.delta_seconds(),
+		}
+	}
+}
+
+/// Decides whether the simulation advances during this frame
+fn tas_step(
+	mut tas: ResMut<Tas>,
+	mut time: ResMut<Time>,
+	mut fixed_time: ResMut<FixedTime>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+	mut start_time: ResMut<StartTime>,
+	recording: Res<ReplayRecording>,
+	q_player: Query<(), With<Player>>,
+) {
+	let tick = recording.0.ticks();
+
+	if tas.play_until.is_some_and(|until| tick >= until) {
+		tas.play_until = None;
+	}
+
+	// Wait for the level to be loaded before simulating anything
+	let advance = std::mem::take(&mut tas.advance);
+	let stepping = !q_player.is_empty() && (advance || tas.playing || tas.play_until.is_some());
+	tas.step = stepping.then_some(tick);
+
+	// The clock only moves forward one tick at a time
+	time.pause();
+	if tas.timestep_mode.is_none() {
+		tas.timestep_mode = Some(rapier_config.timestep_mode);
+	}
+	let substeps = match tas.timestep_mode {
+		Some(TimestepMode::Variable { substeps, .. } | TimestepMode::Fixed { substeps, .. }) => {
+			substeps
+		}
+		_ => 1,
+	};
+	rapier_config.timestep_mode = TimestepMode::Fixed {
+		dt: fixed_time.period.as_secs_f32(),
+		substeps,
+	};
+	rapier_config.physics_pipeline_active = stepping;
+	if stepping {
+		let period = fixed_time.period;
+		fixed_time.tick(period);
+	}
+
+	// Keep the timer & ghosts in sync with the simulation
+	let now = Instant::now();
+	let elapsed = fixed_time.period * tick as u32;
+	start_time.0 = now.checked_sub(elapsed).unwrap_or(now);
+}
+
+/// Feeds the timeline to the player instead of the keyboard/gamepad
+fn tas_input(
+	mut actions: ResMut<Input<Action>>,
+	mut analog: ResMut<AnalogActions>,
+	mut tas: ResMut<Tas>,
+) {
+	actions.clear();
+	analog.0.clear();
+	let Some(tick) = tas.step else {
+		return;
+	};
+	if tas.timeline.len() <= tick {
+		tas.timeline.resize(tick + 1, default());
+	}
+	let frame = &tas.timeline[tick];
+	for action in TAS_ACTIONS {
+		match (frame.0.contains(&action), actions.pressed(action)) {
+			(true, false) => actions.press(action),
+			(false, true) => actions.release(action),
+			_ => {}
+		}
+	}
+}
+
+/// Devices don't drive actions in TAS mode, so the bindings of the tools are read from the keyboard
+fn tas_shortcuts(
+	keys: Res<Input<KeyCode>>,
+	mapping: Res<InputMapping>,
+	mut tas: ResMut<Tas>,
+	recording: Res<ReplayRecording>,
+	mut ev_restart: EventWriter<Restart>,
+) {
+	if mapping.key_just_pressed(Action::TasAdvance, &keys) {
+		tas.advance = true;
+	}
+	if mapping.key_just_pressed(Action::TasPlay, &keys) {
+		tas.playing = !tas.playing;
+	}
+	if mapping.key_just_pressed(Action::TasRewind, &keys) {
+		let tick = recording.0.ticks();
+		tas.replay_until(tick, &mut ev_restart);
+	}
+}
+
+/// Give the clock and physics back to the normal game
+fn tas_exit(
+	state: Res<State<AppState>>,
+	mut tas: ResMut<Tas>,
+	mut time: ResMut<Time>,
+	mut rapier_config: ResMut<RapierConfiguration>,
+) {
+	// Restarting the level stays paused
+	if state.0 == AppState::Game {
+		return;
+	}
+	tas.step = None;
+	tas.playing = false;
+	time.unpause();
+	if let Some(timestep_mode) = tas.timestep_mode.take() {
+		rapier_config.timestep_mode = timestep_mode;
+	}
+	rapier_config.physics_pipeline_active = true;
+}
+
+fn tas_clear(mut tas: ResMut<Tas>) {
+	*tas = Tas::default();
+}
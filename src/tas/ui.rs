@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use super::{Tas, TAS_ACTIONS};
+use crate::{
+	game::Restart,
+	replay::ReplayRecording,
+	savestate::{LoadState, SaveState},
+};
+
+/// Number of ticks displayed before the current one
+const TICKS_BEFORE: usize = 15;
+/// Number of ticks displayed in the timeline
+const TICKS_SHOWN: usize = 60;
+
+/// Piano-roll style timeline editor
+pub fn tas_ui(
+	mut egui_ctx: EguiContexts,
+	mut tas: ResMut<Tas>,
+	recording: Res<ReplayRecording>,
+	mut ev_restart: EventWriter<Restart>,
+	mut ev_save_state: EventWriter<SaveState>,
+	mut ev_load_state: EventWriter<LoadState>,
+) {
+	let current_tick = recording.0.ticks();
+	let first_tick = current_tick.saturating_sub(TICKS_BEFORE);
+
+	egui::Window::new("tas")
+		.movable(false)
+		.collapsible(false)
+		.resizable(false)
+		.title_bar(false)
+		.anchor(egui::Align2::CENTER_BOTTOM, egui::Vec2::new(0.0, -16.0))
+		.show(egui_ctx.ctx_mut(), |ui| {
+			ui.horizontal(|ui| {
+				ui.label(format!("tick {current_tick}"));
+				if ui.button("Restart").clicked() {
+					tas.replay_until(0, &mut ev_restart);
+				}
+				if ui.button("Replay to here").clicked() {
+					tas.replay_until(current_tick, &mut ev_restart);
+				}
+				if ui.button("Step").clicked() {
+					tas.advance = true;
+				}
+				let play_label = match tas.playing {
+					true => "Pause",
+					false => "Play",
+				};
+				if ui.button(play_label).clicked() {
+					tas.playing = !tas.playing;
+				}
+				if ui.button("Save state").clicked() {
+					ev_save_state.send(SaveState { slot: 0 });
+				}
+				if ui.button("Load state").clicked() {
+					ev_load_state.send(LoadState { slot: 0 });
+				}
+			});
+
+			egui::Grid::new("tas-timeline")
+				.spacing(egui::Vec2::new(2.0, 2.0))
+				.show(ui, |ui| {
+					for action in TAS_ACTIONS {
+						ui.label(format!("{action:?}"));
+						for tick in first_tick..first_tick + TICKS_SHOWN {
+							let pressed = tas
+								.timeline
+								.get(tick)
+								.is_some_and(|frame| frame.0.contains(&action));
+							let color = match (pressed, tick == current_tick) {
+								(true, _) => egui::Color32::from_rgb(230, 60, 60),
+								(false, true) => egui::Color32::from_gray(90),
+								(false, false) => egui::Color32::from_gray(40),
+							};
+							let cell = egui::Button::new("")
+								.fill(color)
+								.min_size(egui::Vec2::new(12.0, 24.0));
+							if ui.add(cell).on_hover_text(format!("tick {tick}")).clicked() {
+								tas.toggle(tick, action);
+							}
+						}
+						ui.end_row();
+					}
+				});
+		});
+}
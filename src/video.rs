@@ -0,0 +1,252 @@
+///
+/// Window & UI preferences, applied to the primary window whenever they change
+///
+use std::time::{Duration, Instant};
+
+use bevy::{
+	prelude::*,
+	window::{PresentMode, PrimaryWindow, WindowMode, WindowScaleFactorChanged},
+};
+use bevy_egui::EguiSettings;
+use bevy_hanabi::Spawner;
+use bevy_iced::IcedSettings;
+use serde::{Deserialize, Serialize};
+
+/// Resolutions offered in the settings menu
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+	(1280, 720),
+	(1600, 900),
+	(1920, 1080),
+	(2560, 1440),
+	(3840, 2160),
+];
+
+/// UI scales offered in the settings menu
+pub const UI_SCALES: [f32; 6] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// Effect capacities offered in the settings menu
+pub const EFFECT_CAPACITIES: [u32; 5] = [256, 512, 1024, 2048, 4096];
+
+/// Frame caps offered in the settings menu
+pub const FRAME_CAPS: [Option<u32>; 6] =
+	[None, Some(30), Some(60), Some(120), Some(144), Some(240)];
+
+pub struct VideoPlugin;
+
+impl Plugin for VideoPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(apply_video_settings.run_if(resource_changed::<VideoSettings>()))
+			.add_system(apply_ui_scale)
+			.add_system(limit_frame_rate.in_base_set(CoreSet::Last));
+	}
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+	#[default]
+	Windowed,
+	Borderless,
+	Fullscreen,
+}
+
+impl DisplayMode {
+	pub fn next(self) -> Self {
+		match self {
+			DisplayMode::Windowed => DisplayMode::Borderless,
+			DisplayMode::Borderless => DisplayMode::Fullscreen,
+			DisplayMode::Fullscreen => DisplayMode::Windowed,
+		}
+	}
+}
+
+impl std::fmt::Display for DisplayMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DisplayMode::Windowed => write!(f, "windowed"),
+			DisplayMode::Borderless => write!(f, "borderless"),
+			DisplayMode::Fullscreen => write!(f, "fullscreen"),
+		}
+	}
+}
+
+impl From<DisplayMode> for WindowMode {
+	fn from(mode: DisplayMode) -> Self {
+		match mode {
+			DisplayMode::Windowed => WindowMode::Windowed,
+			DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+			// keeps the chosen resolution instead of the monitor's
+			DisplayMode::Fullscreen => WindowMode::SizedFullscreen,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct VideoSettings {
+	pub mode: DisplayMode,
+	/// Window size, in logical pixels
+	pub resolution: (u32, u32),
+	pub vsync: bool,
+	/// Size of menus & overlays, relative to their default size
+	pub ui_scale: f32,
+	/// Maximum frames per second, unlimited if None
+	pub frame_cap: Option<u32>,
+}
+
+impl Default for VideoSettings {
+	fn default() -> Self {
+		Self {
+			mode: DisplayMode::Windowed,
+			resolution: (1280, 720),
+			vsync: true,
+			ui_scale: 1.0,
+			frame_cap: None,
+		}
+	}
+}
+
+fn apply_video_settings(
+	settings: Res<VideoSettings>,
+	mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+	let Ok(mut window) = q_window.get_single_mut() else {
+		return;
+	};
+	window.mode = settings.mode.into();
+	window.present_mode = match settings.vsync {
+		true => PresentMode::AutoVsync,
+		false => PresentMode::AutoNoVsync,
+	};
+
+	// both are logical sizes, so the window is as large on HiDPI monitors & the scale factor is kept
+	let (width, height) = settings.resolution;
+	if (window.width(), window.height()) != (width as f32, height as f32) {
+		window.resolution.set(width as f32, height as f32);
+	}
+}
+
+/// Scales the UIs, again whenever the window moves to a monitor with another scale factor
+fn apply_ui_scale(
+	settings: Res<VideoSettings>,
+	mut ev_scale_factor_changed: EventReader<WindowScaleFactorChanged>,
+	q_window: Query<&Window, With<PrimaryWindow>>,
+	mut egui_settings: ResMut<EguiSettings>,
+	mut iced_settings: ResMut<IcedSettings>,
+) {
+	if ev_scale_factor_changed.iter().count() == 0 && !settings.is_changed() {
+		return;
+	}
+	let Ok(window) = q_window.get_single() else {
+		return;
+	};
+	egui_settings.scale_factor = settings.ui_scale as f64;
+	// iced replaces the window's scale factor instead of multiplying it
+	iced_settings.set_scale_factor(window.scale_factor() * settings.ui_scale as f64);
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityPreset {
+	Low,
+	Medium,
+	#[default]
+	High,
+	/// Individually chosen settings
+	Custom,
+}
+
+impl QualityPreset {
+	/// The next preset, skipping `Custom` which is reached by editing a setting
+	pub fn next(self) -> Self {
+		match self {
+			QualityPreset::Low => QualityPreset::Medium,
+			QualityPreset::Medium => QualityPreset::High,
+			QualityPreset::High | QualityPreset::Custom => QualityPreset::Low,
+		}
+	}
+}
+
+impl std::fmt::Display for QualityPreset {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			QualityPreset::Low => write!(f, "low"),
+			QualityPreset::Medium => write!(f, "medium"),
+			QualityPreset::High => write!(f, "high"),
+			QualityPreset::Custom => write!(f, "custom"),
+		}
+	}
+}
+
+/// Rendering features & particle budget
+/// Applied when the game camera & particle effects are spawned, so on the next level
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct GraphicsQuality {
+	pub preset: QualityPreset,
+	pub hdr: bool,
+	/// Only visible with HDR
+	pub bloom: bool,
+	pub tonemapping: bool,
+	/// Multiplier of the rate at which effects emit particles
+	pub particle_density: f32,
+	/// Maximum number of live particles of each effect
+	pub effect_capacity: u32,
+}
+
+impl Default for GraphicsQuality {
+	fn default() -> Self {
+		Self::preset(QualityPreset::High)
+	}
+}
+
+impl GraphicsQuality {
+	/// Settings of a preset, `Custom` starts from the high preset
+	pub fn preset(preset: QualityPreset) -> Self {
+		let (hdr, bloom, tonemapping, particle_density, effect_capacity) = match preset {
+			QualityPreset::Low => (false, false, false, 0.25, 256),
+			QualityPreset::Medium => (true, false, true, 0.5, 1024),
+			QualityPreset::High | QualityPreset::Custom => (true, true, true, 1.0, 4096),
+		};
+		Self {
+			preset,
+			hdr,
+			bloom,
+			tonemapping,
+			particle_density,
+			effect_capacity,
+		}
+	}
+
+	/// Spawner emitting `rate` particles per second at full density
+	pub fn spawner(&self, rate: f32) -> Spawner {
+		Spawner::rate((rate * self.particle_density).into())
+	}
+}
+
+/// How early to wake up from sleeping, as the OS may oversleep by about a millisecond
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Waits at the end of the frame, so that frames last at least as long as the cap allows
+/// Present modes can only sync to the monitor's refresh rate, so other caps have to wait on the
+/// main thread: it sleeps for most of the remaining time, then spins until the deadline for
+/// precision. Deadlines follow each other so that oversleeping doesn't lower the frame rate
+fn limit_frame_rate(settings: Res<VideoSettings>, mut deadline: Local<Option<Instant>>) {
+	let Some(fps) = settings.frame_cap else {
+		*deadline = None;
+		return;
+	};
+	let frame_time = Duration::from_secs_f64(1.0 / fps.max(1) as f64);
+	let now = Instant::now();
+	let Some(target) = deadline.filter(|d| *d > now) else {
+		// first frame, or late: start again from now instead of catching up
+		*deadline = Some(now + frame_time);
+		return;
+	};
+
+	if let Some(sleep) = (target - now).checked_sub(SPIN_MARGIN) {
+		std::thread::sleep(sleep);
+	}
+	while Instant::now() < target {
+		std::hint::spin_loop();
+	}
+	*deadline = Some(target + frame_time);
+}